@@ -1,20 +1,52 @@
 use super::project::Project;
-use crate::colors::COLORS;
+use crate::attributes::Attributes;
+use crate::attributes::SchemaEditor;
+use crate::bookmarks;
+use crate::coco;
+use crate::colors::Palette;
+use crate::crops::CropCache;
+use crate::crops::CropSort;
+use crate::display;
+use crate::display::DisplayCache;
+use crate::display::DisplayFilter;
+use crate::label_manager::MANAGE_ICON;
+use crate::loader::ProjectLoader;
+use crate::orientation::OrientationPolicy;
+use crate::project::Image;
 use crate::project::Label;
+use crate::project::Seek;
 use crate::project::Segment;
 use crate::project::Tool;
+use crate::render::BoxStyle;
+use crate::segment_list::SegmentSort;
+use crate::stats::DatasetStats;
+use crate::stats::count_labels;
+use crate::tags;
+use crate::tags::TAG_KEYS;
+use crate::tags::TagEditor;
+use crate::thumbnails::ThumbnailCache;
+use crate::validation;
+use crate::voc;
 use egui::Color32;
 use egui::FontId;
 use egui::Rangef;
 use egui::Sense;
 use egui::TextBuffer;
 use egui_extras::Column;
-use std::path::PathBuf;
+
+/// Side length of the on-screen resize handles, in points.
+const HANDLE_SIZE: f32 = 8.0;
+/// How close, in points, a dragged edge has to get to snap.
+const SNAP_DISTANCE: f32 = 6.0;
 
 pub struct SegmentDrag {
     segment_index: usize,
+    /// Which edges are being dragged, -1, 0 or 1 per axis. Zero moves the selection.
     awesome: egui::Vec2,
     icon: egui::CursorIcon,
+    start_pos: egui::Pos2,
+    /// Normalized bounds of the segment when the drag started.
+    origin: egui::Rect,
 }
 
 #[derive(Copy, Clone)]
@@ -24,16 +56,75 @@ pub struct Input {
     accept: bool,
     delete: bool,
     clone: bool,
+    select_all: bool,
+    deselect: bool,
+    nudge: egui::Vec2,
+    cycle: i32,
+    class_key: Option<usize>,
+    /// Whether the class key moves boxes instead of picking the class.
+    relabel: bool,
+    quick_pick: bool,
+    toggle_annotations: bool,
+    toggle_gallery: bool,
+    toggle_filter_bar: bool,
+    /// What to look for and whether forwards.
+    seek: Option<(Seek, bool)>,
+    /// Jumps of [`Project::jump_size`] images.
+    jump: isize,
+    goto: bool,
+    toggle_flag: bool,
+    tag_key: Option<usize>,
     tool: Option<Tool>,
     hover_pos: Option<egui::Pos2>,
+    pressed: bool,
+    released: bool,
+    modifiers: egui::Modifiers,
     scroll_delta: egui::Vec2,
 }
 
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum View {
+    Editor,
+    Gallery,
+    Crops,
+}
+
 pub struct App {
     pub project: Option<Project>,
     pub smooth_scroll: bool,
     pub advance_on_accept: bool,
     pub message_box: Option<String>,
+    pub show_loupe: bool,
+    pub loupe_zoom: f32,
+    pub snap_edges: bool,
+    pub show_segment_list: bool,
+    pub segment_sort: SegmentSort,
+    pub quick_picker: Option<String>,
+    pub box_style: BoxStyle,
+    pub show_annotations: bool,
+    pub show_label_manager: bool,
+    pub show_stats: bool,
+    pub stats: Option<DatasetStats>,
+    pub display_filter: DisplayFilter,
+    pub display: DisplayCache,
+    /// Image the timeline was last scrolled to.
+    pub timeline_follow: Option<usize>,
+    pub thumbnails: ThumbnailCache,
+    pub loader: Option<ProjectLoader>,
+    pub view: View,
+    pub gallery_tile_size: f32,
+    /// Image the gallery was last scrolled to.
+    pub gallery_follow: Option<usize>,
+    pub crop_class: Option<u32>,
+    pub crop_sort: CropSort,
+    pub crops: CropCache,
+    pub show_filter_bar: bool,
+    pub filter_error: Option<String>,
+    pub show_bookmarks: bool,
+    pub show_tag_editor: bool,
+    pub tag_editor: TagEditor,
+    pub show_attributes: bool,
+    pub schema_editor: SchemaEditor,
 }
 
 impl Default for App {
@@ -43,14 +134,61 @@ impl Default for App {
             smooth_scroll: true,
             advance_on_accept: false,
             message_box: None,
+            show_loupe: false,
+            loupe_zoom: 4.0,
+            snap_edges: true,
+            show_segment_list: true,
+            segment_sort: SegmentSort::default(),
+            quick_picker: None,
+            box_style: BoxStyle::default(),
+            show_annotations: true,
+            show_label_manager: false,
+            show_stats: false,
+            stats: None,
+            display_filter: DisplayFilter::default(),
+            display: DisplayCache::default(),
+            timeline_follow: None,
+            thumbnails: ThumbnailCache::default(),
+            loader: None,
+            view: View::Editor,
+            gallery_tile_size: 160.0,
+            gallery_follow: None,
+            crop_class: None,
+            crop_sort: CropSort::default(),
+            crops: CropCache::default(),
+            show_filter_bar: false,
+            filter_error: None,
+            show_bookmarks: false,
+            show_tag_editor: false,
+            tag_editor: TagEditor::default(),
+            show_attributes: false,
+            schema_editor: SchemaEditor::default(),
         }
     }
 }
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_loader(ctx);
+        self.thumbnails.receive(ctx);
+        self.crops.receive(ctx);
         let input = self.read_inputs(ctx);
 
+        if input.toggle_annotations {
+            self.show_annotations ^= true;
+        }
+
+        if input.toggle_filter_bar {
+            self.show_filter_bar ^= true;
+        }
+
+        if input.toggle_gallery {
+            self.view = match self.view {
+                View::Editor => View::Gallery,
+                View::Gallery | View::Crops => View::Editor,
+            };
+        }
+
         if let Some(project) = &mut self.project {
             if let Some(tool) = input.tool {
                 project.tool = tool;
@@ -63,11 +201,57 @@ impl eframe::App for App {
             if input.right {
                 project.advance();
             }
+
+            if let Some((seek, forward)) = input.seek {
+                project.seek(seek, forward);
+            }
+
+            if input.jump != 0 {
+                project.jump(input.jump * project.jump_size as isize);
+            }
+
+            if input.toggle_flag {
+                let flag = project.flag.clone();
+                project.toggle_flag(&flag);
+            }
+
+            if let Some(key) = input.tag_key
+                && let Some(&(group, tag)) = tags::tag_keys(project).get(key)
+            {
+                project.toggle_tag(group, tag);
+            }
+
+            if input.goto {
+                ctx.memory_mut(|m| m.request_focus(egui::Id::new("goto")));
+            }
         }
 
         self.sidebar(ctx);
-        self.central_panel(ctx, input);
+        self.filter_bar(ctx);
+        self.status_bar(ctx, input);
+        self.segment_list(ctx);
+        match self.view {
+            View::Editor => self.central_panel(ctx, input),
+            View::Gallery => self.gallery(ctx),
+            View::Crops => self.crop_gallery(ctx),
+        }
+        // Boxes are edited elsewhere, so the crop gallery starts afresh.
+        if self.view != View::Crops
+            && let Some(project) = &mut self.project
+        {
+            project.crop_boxes = None;
+        }
+        self.prefetch(ctx);
         self.timeline(ctx);
+        if input.quick_pick && self.project.is_some() {
+            self.quick_picker = Some(String::new());
+        }
+        self.quick_picker(ctx);
+        self.label_manager(ctx);
+        self.stats_window(ctx);
+        self.bookmarks_window(ctx);
+        self.tag_editor_window(ctx);
+        self.attribute_window(ctx);
         if let Some(project) = &mut self.project
             && let Some((id, name)) = &mut project.add_label_modal
         {
@@ -98,8 +282,13 @@ impl eframe::App for App {
                     if let std::collections::hash_map::Entry::Vacant(e) = project.labels.entry(id) {
                         e.insert(Label {
                             name,
-                            color: COLORS[id as usize % COLORS.len()],
+                            color: project.palette.color(id),
+                            custom_color: false,
+                            visible: true,
                         });
+                        if let Some(key) = project.hotkeys.iter().position(Option::is_none) {
+                            project.bind_hotkey(key, id);
+                        }
                     } else {
                         self.message_box = Some("This ID is taken already".to_string());
                         return;
@@ -110,23 +299,39 @@ impl eframe::App for App {
             }
         }
         self.msg_box(ctx);
+        self.thumbnails.evict();
+        self.crops.evict();
     }
 }
 
 impl App {
-    pub fn open_project(&mut self, yaml_file_path: PathBuf) {
-        let loaded_project = Project::load(yaml_file_path);
-        match loaded_project {
-            Ok(project) => {
-                self.project = Some(project);
-            }
-            Err(err) => {
-                self.message_box = Some(format!("{}", err));
-            }
+    /// Keeps the images around the current one decoded, so stepping through
+    /// them doesn't wait on the decoder.
+    fn prefetch(&mut self, ctx: &egui::Context) {
+        let Some(project) = &mut self.project else {
+            return;
+        };
+        // The images next to the current one as they are listed.
+        let mut indices = vec![project.image_index];
+        if let Some(position) = project.position() {
+            let first = position.saturating_sub(1);
+            let last = (position + 2).min(project.order.len());
+            indices = project.order[first..last].to_vec();
+        }
+        for &i in &indices {
+            let image = &mut project.images[i];
+            image
+                .high_bit_depth
+                .get_or_insert_with(|| display::probe_high_bit_depth(&image.file_path));
         }
+        let images: Vec<&Image> = indices.iter().map(|&i| &project.images[i]).collect();
+        self.display.prefetch(ctx, &images, &self.display_filter);
     }
 
     pub fn label_segment(&mut self, image_rect: egui::Rect, label_id: u32, rect: egui::Rect) {
+        // Stamping near a border clips the box, but not the size remembered
+        // for the class.
+        let stamp_size = rect.size() / image_rect.size();
         let rect = rect.intersect(image_rect);
         // boohoo
         let project = self.project.as_mut().unwrap();
@@ -143,7 +348,13 @@ impl App {
             label_id,
             center,
             size,
+            selected: false,
+            hidden: false,
+            confidence: None,
+            track_id: None,
+            attributes: Attributes::default(),
         });
+        project.stamp_sizes.insert(label_id, stamp_size);
 
         if self.advance_on_accept {
             project.advance();
@@ -151,16 +362,66 @@ impl App {
     }
 
     pub fn read_inputs(&mut self, ctx: &egui::Context) -> Input {
+        let typing = ctx.wants_keyboard_input();
         ctx.input(|r| {
-            let key = |k| r.key_pressed(k);
+            let key = |k| !typing && r.key_pressed(k);
 
             use egui::Key::*;
+            let step = if r.modifiers.shift { 10.0 } else { 1.0 };
+            let mut nudge = egui::Vec2::ZERO;
+            if key(ArrowLeft) {
+                nudge.x -= step;
+            }
+            if key(ArrowRight) {
+                nudge.x += step;
+            }
+            if key(ArrowUp) {
+                nudge.y -= step;
+            }
+            if key(ArrowDown) {
+                nudge.y += step;
+            }
+            let class_key = if r.modifiers.alt {
+                None
+            } else {
+                DIGIT_KEYS.iter().position(|&k| key(k))
+            };
             Input {
-                left: key(A),
+                class_key,
+                relabel: r.modifiers.command,
+                quick_pick: key(K) && r.modifiers.command,
+                toggle_annotations: key(H),
+                toggle_gallery: key(G) && !r.modifiers.command,
+                goto: key(G) && r.modifiers.command,
+                toggle_flag: key(B),
+                tag_key: TAG_KEYS.iter().position(|&k| key(k)),
+                seek: if key(N) {
+                    Some((Seek::Unlabelled, !r.modifiers.shift))
+                } else if key(M) {
+                    Some((Seek::Class, !r.modifiers.shift))
+                } else if key(V) {
+                    Some((Seek::Problem, !r.modifiers.shift))
+                } else {
+                    None
+                },
+                jump: key(PageDown) as isize - key(PageUp) as isize,
+                toggle_filter_bar: key(F) && r.modifiers.command,
+                left: key(A) && !r.modifiers.command,
                 right: key(D),
                 accept: key(Space),
-                delete: key(X),
+                delete: key(X) || key(Delete),
                 clone: key(C),
+                select_all: key(A) && r.modifiers.command,
+                deselect: key(Escape),
+                nudge,
+                cycle: if key(Tab) {
+                    if r.modifiers.shift { -1 } else { 1 }
+                } else if r.modifiers.alt && r.raw_scroll_delta != egui::Vec2::ZERO {
+                    let delta = r.raw_scroll_delta.x + r.raw_scroll_delta.y;
+                    -delta.signum() as i32
+                } else {
+                    0
+                },
                 tool: if key(Q) {
                     Some(Tool::Stamp)
                 } else if key(W) {
@@ -171,6 +432,9 @@ impl App {
                     None
                 },
                 hover_pos: r.pointer.hover_pos(),
+                pressed: r.pointer.primary_pressed(),
+                released: r.pointer.primary_released(),
+                modifiers: r.modifiers,
                 scroll_delta: if self.smooth_scroll {
                     r.smooth_scroll_delta
                 } else {
@@ -200,7 +464,7 @@ impl App {
     }
 
     fn save_load_buttons(&mut self, ui: &mut egui::Ui) {
-        ui.columns(2, |ui| {
+        ui.columns(3, |ui| {
             ui[0].vertical_centered_justified(|ui| {
                 if ui
                     .add(egui::Button::new(""))
@@ -231,6 +495,49 @@ impl App {
                     });
                 }
             });
+            ui[2].vertical_centered_justified(|ui| {
+                let Some(project) = &mut self.project else {
+                    ui.add_enabled(false, egui::Button::new(""));
+                    return;
+                };
+                let mut result = Ok(None);
+                // Images whose labels haven't been read yet would come out empty.
+                let loading = self.loader.is_some();
+                ui.add_enabled_ui(!loading, |ui| {
+                    ui.menu_button("", |ui| {
+                        if ui.button("COCO json…").clicked()
+                            && let Some(path) = rfd::FileDialog::new()
+                                .add_filter("json", &["json"])
+                                .save_file()
+                        {
+                            result = coco::export(project, &path).map(Some);
+                        }
+                        if ui.button("Pascal VOC…").clicked()
+                            && let Some(dir) = rfd::FileDialog::new().pick_folder()
+                        {
+                            result = voc::export(project, &dir).map(Some);
+                        }
+                    })
+                    .response
+                    .on_hover_text("Export")
+                    .on_disabled_hover_text("Wait for the labels to load");
+                });
+                match result {
+                    Ok(Some(count)) => {
+                        let mut message = format!("Exported {} images", count);
+                        let skipped = project.images.len() - count;
+                        if skipped > 0 {
+                            message.push_str(&format!(
+                                "\nSkipped {} images whose labels or size weren't read",
+                                skipped
+                            ));
+                        }
+                        self.message_box = Some(message);
+                    }
+                    Ok(None) => {}
+                    Err(err) => self.message_box = Some(format!("{}", err)),
+                }
+            });
         });
     }
 
@@ -238,46 +545,140 @@ impl App {
         if let Some(project) = &mut self.project {
             ui.vertical_centered_justified(|ui| {
                 ui.heading("Labels");
+                let mut picked = None;
+                let mut bind = None;
+                let mut toggle_visible = None;
+                let mut toggle_solo = None;
+                let mut recolor = None;
+                let mut label_ids: Vec<u32> = project.labels.keys().copied().collect();
+                label_ids.sort();
+                let image_counts = project
+                    .images
+                    .get(project.image_index)
+                    .map(|image| count_labels([image]))
+                    .unwrap_or_default();
+                let dataset_counts = project.label_counts();
                 egui_extras::TableBuilder::new(ui)
-                    // .column(Column::auto())
+                    .column(Column::auto())
+                    .column(Column::auto())
                     .column(Column::remainder())
-                    // .column(Column::auto())
-                    // .column(Column::auto())
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .column(Column::auto())
                     .body(|mut b| {
-                        for (&i, label) in project.labels.iter() {
+                        for &i in &label_ids {
+                            let Some(label) = project.labels.get(&i) else {
+                                continue;
+                            };
                             b.row(16.0, |mut row| {
-                                // row.col(|ui| {
-                                //     ui.checkbox(&mut false, ());
-                                // });
+                                row.col(|ui| {
+                                    let mut visible = label.visible;
+                                    if ui
+                                        .checkbox(&mut visible, ())
+                                        .on_hover_text("Show")
+                                        .changed()
+                                    {
+                                        toggle_visible = Some(i);
+                                    }
+                                });
+                                row.col(|ui| {
+                                    let mut color = label.color;
+                                    if egui::color_picker::color_edit_button_srgba(
+                                        ui,
+                                        &mut color,
+                                        egui::color_picker::Alpha::Opaque,
+                                    )
+                                    .changed()
+                                    {
+                                        recolor = Some((i, color));
+                                    }
+                                });
                                 row.col(|ui| {
                                     ui.style_mut().visuals.override_text_color = Some(label.color);
+                                    let res = ui.add(
+                                        egui::Button::new(&label.name)
+                                            .selected(project.label_id == Some(i)),
+                                    );
+                                    if res.clicked() {
+                                        picked = Some(i);
+                                    };
+                                    if res.hovered()
+                                        && let Some(key) = ui.input(|r| {
+                                            DIGIT_KEYS
+                                                .iter()
+                                                .position(|&k| r.modifiers.alt && r.key_pressed(k))
+                                        })
+                                    {
+                                        bind = Some((key, i));
+                                    }
+                                });
+                                row.col(|ui| {
+                                    if let Some(key) =
+                                        project.hotkeys.iter().position(|&h| h == Some(i))
+                                    {
+                                        ui.weak(DIGIT_NAMES[key]);
+                                    }
+                                });
+                                row.col(|ui| {
                                     if ui
-                                        .add(
-                                            egui::Button::new(&label.name)
-                                                .selected(project.label_id == Some(i)),
-                                        )
+                                        .selectable_label(project.solo_label == Some(i), "")
+                                        .on_hover_text("Solo")
                                         .clicked()
                                     {
-                                        project.label_id = Some(i);
-                                    };
+                                        toggle_solo = Some(i);
+                                    }
+                                });
+                                row.col(|ui| {
+                                    ui.label(image_counts.get(&i).unwrap_or(&0).to_string())
+                                        .on_hover_text("Boxes in this image");
+                                });
+                                row.col(|ui| {
+                                    ui.label(dataset_counts.get(&i).unwrap_or(&0).to_string())
+                                        .on_hover_text("Boxes in the dataset");
                                 });
-                                // row.col(|ui| {
-                                //     ui.label("0");
-                                // });
-                                // row.col(|ui| {
-                                //     ui.label("0");
-                                // });
                             });
                         }
                         b.row(16.0, |mut row| {
+                            row.col(|_| {});
+                            row.col(|_| {});
                             row.col(|ui| {
                                 if ui.add(egui::Button::new("+")).clicked() {
                                     project.add_label_modal =
-                                        Some((1 + project.labels.len() as u32, String::new()));
+                                        Some((project.next_label_id(), String::new()));
                                 };
                             });
+                            row.col(|ui| {
+                                if ui
+                                    .small_button(MANAGE_ICON)
+                                    .on_hover_text("Manage classes")
+                                    .clicked()
+                                {
+                                    self.show_label_manager ^= true;
+                                }
+                            });
                         });
                     });
+                if let Some(i) = picked {
+                    project.select_label(i);
+                }
+                if let Some((key, i)) = bind {
+                    project.bind_hotkey(key, i);
+                }
+                if let Some(i) = toggle_visible
+                    && let Some(label) = project.labels.get_mut(&i)
+                {
+                    label.visible ^= true;
+                }
+                if let Some((i, color)) = recolor
+                    && let Some(label) = project.labels.get_mut(&i)
+                {
+                    label.color = color;
+                    label.custom_color = true;
+                }
+                if let Some(i) = toggle_solo {
+                    project.solo_label = (project.solo_label != Some(i)).then_some(i);
+                }
                 ui.style_mut().visuals.override_text_color = None;
                 ui.separator();
                 ui.heading("Tools");
@@ -301,7 +702,59 @@ impl App {
                 ui.heading("Options");
                 ui.horizontal(|ui| ui.checkbox(&mut self.smooth_scroll, "Smooth scroll"));
                 ui.horizontal(|ui| ui.checkbox(&mut self.advance_on_accept, "Quick advance"));
+                ui.horizontal(|ui| ui.checkbox(&mut project.lock_aspect, "Lock stamp aspect"));
+                ui.horizontal(|ui| {
+                    let mut palette = project.palette;
+                    egui::ComboBox::from_label("Palette")
+                        .selected_text(palette.name())
+                        .show_ui(ui, |ui| {
+                            for p in [Palette::Distinct, Palette::ColorBlindSafe] {
+                                ui.selectable_value(&mut palette, p, p.name());
+                            }
+                        });
+                    if palette != project.palette {
+                        project.set_palette(palette);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label("EXIF")
+                        .selected_text(project.orientation_policy.name())
+                        .show_ui(ui, |ui| {
+                            for p in [OrientationPolicy::Upright, OrientationPolicy::Raw] {
+                                ui.selectable_value(&mut project.orientation_policy, p, p.name());
+                            }
+                        })
+                        .response
+                        .on_hover_text(
+                            "Images are always shown upright. This picks how boxes are \
+                             written to label files on save.",
+                        );
+                });
+                ui.horizontal(|ui| ui.checkbox(&mut self.show_loupe, "Loupe"));
+                ui.horizontal(|ui| ui.checkbox(&mut self.snap_edges, "Snap edges"));
+                ui.horizontal(|ui| ui.checkbox(&mut self.show_segment_list, "Box list"));
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.show_filter_bar, "Filter");
+                    if project.filter.is_active() {
+                        ui.label(format!(
+                            "{} / {}",
+                            project.order.len(),
+                            project.images.len()
+                        ));
+                    }
+                });
+                ui.collapsing("Box style", |ui| self.box_style.ui(ui));
+                ui.collapsing("Display", |ui| self.display_filter.ui(ui));
+                ui.horizontal(|ui| ui.checkbox(&mut self.show_stats, " Statistics"));
+                ui.horizontal(|ui| ui.checkbox(&mut self.show_bookmarks, " Bookmarks"));
+                ui.horizontal(|ui| ui.checkbox(&mut self.show_tag_editor, " Image tags"));
+                ui.horizontal(|ui| ui.checkbox(&mut self.show_attributes, " Box attributes"));
                 ui.separator();
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.view, View::Editor, "Editor");
+                    ui.selectable_value(&mut self.view, View::Gallery, "Gallery");
+                    ui.selectable_value(&mut self.view, View::Crops, "Crops");
+                });
                 // ui.columns(3, |ui| {
                 //     ui[0].vertical_centered_justified(|ui| {
                 //         ui.add(egui::Button::new("Train").selected(false))
@@ -315,13 +768,14 @@ impl App {
                 // });
                 nav_buttons(ui, project);
                 ui.vertical(|ui| {
-                    if let Some(image) = project.images.get(project.image_index) {
-                        if ui.link(image.file_name.clone()).clicked() {
-                            if let Err(err) = open::that(&image.file_path) {
-                                println!("{}", err);
-                            }
-                        }
+                    if let Some(image) = project.images.get(project.image_index)
+                        && ui.link(image.file_name.clone()).clicked()
+                        && let Err(err) = open::that(&image.file_path)
+                    {
+                        println!("{}", err);
                     }
+                    bookmarks::image_notes(ui, project);
+                    tags::image_tags(ui, project);
                 });
             });
         }
@@ -330,108 +784,360 @@ impl App {
     pub fn central_panel(&mut self, ctx: &egui::Context, input: Input) {
         egui::CentralPanel::default().show(ctx, |ui| {
             if let Some(project) = &mut self.project {
-                if project.tool == Tool::Stamp {
-                    let var_name = 0.25;
+                let hidden_labels: Vec<u32> = project
+                    .labels
+                    .keys()
+                    .copied()
+                    .filter(|&id| !project.label_visible(id))
+                    .collect();
 
-                    project.rect_size += egui::Vec2::splat(input.scroll_delta.y) * var_name;
-                    project.rect_size.x += input.scroll_delta.x * var_name;
+                if project.drag_image_index != project.image_index {
+                    project.drag_image_index = project.image_index;
+                    project.drag_start_pos = None;
+                    project.edit_drag = None;
+                    project.select_drag_start = None;
                 }
 
-                project.rect_size = project.rect_size.max(egui::Vec2 { x: 2.0, y: 2.0 });
-
                 let Some(image) = &mut project.images.get_mut(project.image_index) else {
                     return;
                 };
 
+                image
+                    .high_bit_depth
+                    .get_or_insert_with(|| display::probe_high_bit_depth(&image.file_path));
+                let source: egui::ImageSource =
+                    if !display::needs_decoder(image, &self.display_filter) {
+                        (&image.uri).into()
+                    } else {
+                        match self.display.texture(
+                            ui.ctx(),
+                            &image.file_path,
+                            image.orientation,
+                            &self.display_filter,
+                        ) {
+                            Ok(Some(texture)) => texture.into(),
+                            Ok(None) => {
+                                ui.centered_and_justified(|ui| ui.spinner());
+                                return;
+                            }
+                            Err(err) => {
+                                println!("{}", err);
+                                (&image.uri).into()
+                            }
+                        }
+                    };
+
                 let res =
                     // ui .centered_and_justified(|ui| {
-                        ui.add(egui::Image::new(&image.uri).sense(Sense::click_and_drag()));
+                        ui.add(egui::Image::new(source.clone()).sense(Sense::click_and_drag()));
                 // })
                 // .inner;
                 let image_rect = res.rect;
+                project.view_rect = Some(image_rect);
+
+                let texture =
+                    match egui::Image::new(source).load_for_size(ui.ctx(), image_rect.size()) {
+                        Ok(egui::load::TexturePoll::Ready { texture }) => Some(texture),
+                        _ => None,
+                    };
+                if let Some(texture) = texture {
+                    image.pixel_size = Some(texture.size);
+                }
+
+                if project.tool == Tool::Stamp && !input.modifiers.alt {
+                    let var_name = 0.25;
+                    let size = project.rect_size * image_rect.size();
+                    let size = if project.lock_aspect {
+                        let delta = input.scroll_delta.x + input.scroll_delta.y;
+                        size * ((size.x + delta * var_name) / size.x)
+                    } else {
+                        size + egui::Vec2::new(
+                            input.scroll_delta.x + input.scroll_delta.y,
+                            input.scroll_delta.y,
+                        ) * var_name
+                    };
+                    if size.min_elem() >= 2.0 {
+                        project.rect_size = size / image_rect.size();
+                    }
+                }
+
+                let show_annotations = self.show_annotations;
+                let shown = |s: &Segment| {
+                    show_annotations && !s.hidden && !hidden_labels.contains(&s.label_id)
+                };
+                let any_selected = image.segments.iter().any(|s| s.selected && shown(s));
 
-                let mut highlighted_segment = None;
+                let mut hovered_segments = vec![];
                 for (i, segment) in image.segments.iter().enumerate() {
+                    if !shown(segment) {
+                        continue;
+                    }
                     let rect = fun_name(image_rect, segment);
-                    let label = &project.labels[&segment.label_id];
-                    let color = label.color;
-                    ui.painter().debug_rect(rect, color, &label.name);
+                    let (name, color) = project
+                        .labels
+                        .get(&segment.label_id)
+                        .map_or(("?", Color32::GRAY), |l| (l.name.as_str(), l.color));
+                    self.box_style.draw(
+                        ui.painter(),
+                        rect,
+                        color,
+                        &self.box_style.caption(name, segment),
+                        self.box_style.dim_unselected && any_selected && !segment.selected,
+                    );
+                    if segment.selected {
+                        ui.painter().rect_stroke(
+                            rect,
+                            0.0,
+                            (2.0, Color32::WHITE),
+                            egui::StrokeKind::Outside,
+                        );
+                    }
 
                     if let Some(hover_pos) = input.hover_pos
                         && rect.contains(hover_pos)
                     {
-                        highlighted_segment = Some((i, rect, segment.label_id, color));
+                        hovered_segments.push((i, rect, segment.label_id, color));
+                    }
+                }
+
+                // Smallest box first, so inner boxes are reachable without cycling.
+                hovered_segments.sort_by(|a, b| a.1.area().total_cmp(&b.1.area()));
+                let candidates: Vec<usize> = hovered_segments.iter().map(|s| s.0).collect();
+                if candidates != project.hover_candidates {
+                    project.hover_candidates = candidates;
+                    project.hover_cycle = 0;
+                }
+                if !hovered_segments.is_empty() {
+                    project.hover_cycle = (project.hover_cycle as i32 + input.cycle)
+                        .rem_euclid(hovered_segments.len() as i32)
+                        as usize;
+                }
+                let highlighted_segment = hovered_segments.get(project.hover_cycle).copied();
+                if hovered_segments.len() > 1
+                    && let Some((_, rect, _, color)) = highlighted_segment
+                {
+                    ui.painter().text(
+                        rect.right_top(),
+                        egui::Align2::RIGHT_BOTTOM,
+                        format!("{}/{}", project.hover_cycle + 1, hovered_segments.len()),
+                        FontId::monospace(12.0),
+                        color,
+                    );
+                }
+
+                if input.select_all {
+                    image.segments.iter_mut().for_each(|s| s.selected = true);
+                }
+                if input.deselect {
+                    image.segments.iter_mut().for_each(|s| s.selected = false);
+                }
+                if input.nudge != egui::Vec2::ZERO {
+                    let step = input.nudge / image.pixel_size.unwrap_or(image_rect.size());
+                    for segment in image.segments.iter_mut().filter(|s| s.selected && shown(s)) {
+                        segment.center += step;
                     }
                 }
 
                 if project.tool == Tool::Edit {
-                    if let Some(drag) = &project.edit_drag
-                        && res.dragged()
-                    {
-                        let delta = res.drag_delta();
-                        let segment = &mut image.segments[drag.segment_index];
+                    if let Some(drag) = &project.edit_drag {
                         if drag.awesome == egui::Vec2::ZERO {
-                            segment.center += delta / image_rect.size();
-                        } else {
-                            let shift = delta * drag.awesome.abs() / 2.0;
-                            let resize = delta * drag.awesome;
-                            segment.size += resize;
-                            segment.center += shift;
+                            if res.dragged() {
+                                let delta = res.drag_delta() / image_rect.size();
+                                for segment in
+                                    image.segments.iter_mut().filter(|s| s.selected && shown(s))
+                                {
+                                    segment.center += delta;
+                                }
+                            }
+                        } else if let Some(hover_pos) = input.hover_pos {
+                            // Image borders and the edges of every other box.
+                            let (mut xs, mut ys) = (vec![], vec![]);
+                            if self.snap_edges {
+                                xs.extend([0.0, 1.0]);
+                                ys.extend([0.0, 1.0]);
+                                for (_, other) in image
+                                    .segments
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|(i, s)| *i != drag.segment_index && shown(s))
+                                {
+                                    let half = other.size / 2.0;
+                                    xs.extend([other.center.x - half.x, other.center.x + half.x]);
+                                    ys.extend([other.center.y - half.y, other.center.y + half.y]);
+                                }
+                            }
+
+                            let offset = (hover_pos - drag.start_pos) / image_rect.size();
+                            let tolerance = egui::Vec2::splat(SNAP_DISTANCE) / image_rect.size();
+                            let min_size = egui::Vec2::splat(2.0) / image_rect.size();
+                            let mut rect = drag.origin;
+                            if drag.awesome.x < 0.0 {
+                                rect.min.x = snap(rect.min.x + offset.x, &xs, tolerance.x)
+                                    .min(rect.max.x - min_size.x);
+                            } else if drag.awesome.x > 0.0 {
+                                rect.max.x = snap(rect.max.x + offset.x, &xs, tolerance.x)
+                                    .max(rect.min.x + min_size.x);
+                            }
+                            if drag.awesome.y < 0.0 {
+                                rect.min.y = snap(rect.min.y + offset.y, &ys, tolerance.y)
+                                    .min(rect.max.y - min_size.y);
+                            } else if drag.awesome.y > 0.0 {
+                                rect.max.y = snap(rect.max.y + offset.y, &ys, tolerance.y)
+                                    .max(rect.min.y + min_size.y);
+                            }
+
+                            if let Some(segment) = image.segments.get_mut(drag.segment_index) {
+                                segment.center = rect.center();
+                                segment.size = rect.size();
+                            }
                         }
 
                         ui.ctx().set_cursor_icon(drag.icon);
-                    } else if res.drag_stopped() {
-                        project.edit_drag = None;
-                    } else if let Some(hover_pos) = input.hover_pos
-                        && let Some(segment) = highlighted_segment
-                    {
-                        let (i, rect, _, _) = segment;
-                        let pos = (hover_pos - rect.min) / rect.size() * 3.0;
-                        let (x, y) = (pos.x as i32, pos.y as i32);
-                        let icon = match (x, y) {
-                            (0, 0) => egui::CursorIcon::ResizeNorthWest,
-                            (1, 0) => egui::CursorIcon::ResizeNorth,
-                            (2, 0) => egui::CursorIcon::ResizeNorthEast,
-                            (0, 1) => egui::CursorIcon::ResizeWest,
-                            (1, 1) => egui::CursorIcon::Move,
-                            (2, 1) => egui::CursorIcon::ResizeEast,
-                            (0, 2) => egui::CursorIcon::ResizeSouthWest,
-                            (1, 2) => egui::CursorIcon::ResizeSouth,
-                            (2, 2) => egui::CursorIcon::ResizeSouthEast,
-                            _ => egui::CursorIcon::Default,
-                        };
-                        ui.ctx().set_cursor_icon(icon);
-                        if res.drag_started() || res.is_pointer_button_down_on() {
-                            project.edit_drag = Some(SegmentDrag {
-                                segment_index: i,
-                                awesome: egui::Vec2::new((x - 1) as f32, (y - 1) as f32)
-                                    / image_rect.size(),
-                                icon,
-                            });
+                        if input.released {
+                            project.edit_drag = None;
+                        }
+                    } else if let Some(select_start) = project.select_drag_start {
+                        if let Some(hover_pos) = input.hover_pos {
+                            let band = egui::Rect::from_two_pos(select_start, hover_pos);
+                            ui.painter().rect_stroke(
+                                band,
+                                0.0,
+                                (1.0, Color32::WHITE),
+                                egui::StrokeKind::Middle,
+                            );
+                            if input.released {
+                                for segment in image.segments.iter_mut().filter(|s| shown(s)) {
+                                    if band.intersects(fun_name(image_rect, segment)) {
+                                        segment.selected = true;
+                                    }
+                                }
+                            }
+                        }
+                        if input.released {
+                            project.select_drag_start = None;
+                        }
+                    } else if let Some(hover_pos) = input.hover_pos {
+                        let handle_owners: Vec<usize> = image
+                            .segments
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, s)| s.selected && shown(s))
+                            .map(|(i, _)| i)
+                            .chain(highlighted_segment.map(|s| s.0))
+                            .collect();
+                        for &i in &handle_owners {
+                            let rect = fun_name(image_rect, &image.segments[i]);
+                            let color = project
+                                .labels
+                                .get(&image.segments[i].label_id)
+                                .map_or(Color32::GRAY, |l| l.color);
+                            for handle in handles(rect) {
+                                ui.painter().rect(
+                                    handle,
+                                    0.0,
+                                    Color32::WHITE,
+                                    (1.0, color),
+                                    egui::StrokeKind::Inside,
+                                );
+                            }
+                        }
+
+                        let grabbed = handle_owners
+                            .iter()
+                            .find_map(|&i| {
+                                let rect = fun_name(image_rect, &image.segments[i]);
+                                handle_at(rect, hover_pos).map(|dir| (i, dir))
+                            })
+                            .or_else(|| highlighted_segment.map(|s| (s.0, egui::Vec2::ZERO)));
+
+                        if let Some((i, dir)) = grabbed {
+                            let icon = handle_icon(dir);
+                            ui.ctx().set_cursor_icon(icon);
+                            if input.pressed && res.hovered() {
+                                if input.modifiers.command {
+                                    image.segments[i].selected ^= true;
+                                } else {
+                                    if !image.segments[i].selected && !input.modifiers.shift {
+                                        image.segments.iter_mut().for_each(|s| s.selected = false);
+                                    }
+                                    let segment = &mut image.segments[i];
+                                    segment.selected = true;
+                                    project.edit_drag = Some(SegmentDrag {
+                                        segment_index: i,
+                                        awesome: dir,
+                                        icon,
+                                        start_pos: hover_pos,
+                                        origin: egui::Rect::from_center_size(
+                                            segment.center,
+                                            segment.size,
+                                        ),
+                                    });
+                                }
+                            }
+                        } else if input.pressed && res.hovered() {
+                            if !input.modifiers.shift {
+                                image.segments.iter_mut().for_each(|s| s.selected = false);
+                            }
+                            project.select_drag_start = Some(hover_pos);
                         }
                     }
                 }
 
-                if let Some((i, rect, label, color)) = highlighted_segment {
-                    ui.painter()
-                        .rect_stroke(rect, 0.0, (3.0, color), egui::StrokeKind::Middle);
-                    if input.delete {
+                if input.delete {
+                    if image.segments.iter().any(|s| s.selected && shown(s)) {
+                        image.segments.retain(|s| !(s.selected && shown(s)));
+                    } else if let Some((i, _, _, _)) = highlighted_segment {
                         image.segments.remove(i);
                     }
+                    project.edit_drag = None;
+                }
+
+                if let Some((_, rect, label, color)) = highlighted_segment {
+                    ui.painter()
+                        .rect_stroke(rect, 0.0, (3.0, color), egui::StrokeKind::Middle);
                     if input.clone {
-                        project.rect_size = rect.size();
-                        project.label_id = Some(label)
+                        project.select_label(label);
+                        project.rect_size = rect.size() / image_rect.size();
                     }
                 }
 
+                if let Some(label_id) = input.class_key.and_then(|k| project.hotkeys[k]) {
+                    project.assign_label(label_id, input.relabel, highlighted_segment.map(|s| s.0));
+                }
+
+                if self.show_loupe
+                    && let (Some(texture), Some(hover_pos)) = (texture, input.hover_pos)
+                    && image_rect.contains(hover_pos)
+                {
+                    let color = project
+                        .label_id
+                        .and_then(|id| project.labels.get(&id))
+                        .map_or(Color32::WHITE, |l| l.color);
+                    loupe(
+                        ui.ctx(),
+                        &mut self.loupe_zoom,
+                        texture,
+                        image_rect,
+                        hover_pos,
+                        project.drag_start_pos,
+                        color,
+                    );
+                }
+
                 if let (Some(hover_pos), Some(label_id)) = (input.hover_pos, project.label_id) {
-                    let label = &project.labels[&label_id];
+                    let (name, color) = project
+                        .labels
+                        .get(&label_id)
+                        .map_or(("?", Color32::GRAY), |l| (l.name.as_str(), l.color));
                     if project.tool == Tool::Stamp {
-                        let rect = egui::Rect::from_center_size(hover_pos, project.rect_size);
-                        let rect = rect.intersect(image_rect);
-                        ui.painter().debug_rect(rect, label.color, &label.name);
+                        let stamp = egui::Rect::from_center_size(
+                            hover_pos,
+                            project.rect_size * image_rect.size(),
+                        );
+                        let rect = stamp.intersect(image_rect);
+                        self.box_style.draw(ui.painter(), rect, color, name, false);
                         if input.accept || res.clicked() {
-                            self.label_segment(image_rect, label_id, rect);
+                            self.label_segment(image_rect, label_id, stamp);
                         }
                     } else if project.tool == Tool::Drag {
                         if res.drag_started() {
@@ -441,27 +1147,27 @@ impl App {
                         painter.hline(
                             Rangef::new(image_rect.left(), image_rect.right()),
                             hover_pos.y,
-                            (1.0, label.color),
+                            (1.0, color),
                         );
                         painter.vline(
                             hover_pos.x,
                             Rangef::new(image_rect.top(), image_rect.bottom()),
-                            (1.0, label.color),
+                            (1.0, color),
                         );
-                        painter.circle_filled(hover_pos, 3.0, label.color);
+                        painter.circle_filled(hover_pos, 3.0, color);
                         painter.text(
                             hover_pos,
                             egui::Align2::LEFT_BOTTOM,
-                            label.name.clone(),
+                            name,
                             FontId::monospace(12.0),
-                            label.color,
+                            color,
                         );
                         if let Some(drag_start_pos) = project.drag_start_pos {
                             let rect = egui::Rect::from_two_pos(drag_start_pos, hover_pos);
-                            painter.hline(Rangef::NOTHING, 100.0, (10.0, label.color));
-                            painter.debug_rect(rect, label.color, &label.name);
-                            painter.circle_filled(drag_start_pos, 3.0, label.color);
-                            painter.circle_filled(hover_pos, 3.0, label.color);
+                            painter.hline(Rangef::NOTHING, 100.0, (10.0, color));
+                            self.box_style.draw(painter, rect, color, name, false);
+                            painter.circle_filled(drag_start_pos, 3.0, color);
+                            painter.circle_filled(hover_pos, 3.0, color);
                             if res.drag_stopped() {
                                 project.drag_start_pos = None;
                                 self.label_segment(image_rect, label_id, rect);
@@ -473,34 +1179,45 @@ impl App {
         });
     }
 
-    pub fn timeline(&mut self, ctx: &egui::Context) {
-        egui::TopBottomPanel::bottom("timeline").show(ctx, |ui| {
-            if let Some(project) = &mut self.project {
-                let image_size = egui::Vec2::new(16.0, 9.0) * 4.0;
-                let clip_rect = ui.clip_rect();
-                let spacing = ui.spacing().item_spacing.x;
-                // let offset = (image_size.x + spacing) * self.image_index as f32;
-                let visible_images = (clip_rect.width() / (image_size.x + spacing)).ceil() as usize;
-                let skip_images = project.image_index.saturating_sub(visible_images / 2);
+    pub fn status_bar(&mut self, ctx: &egui::Context, input: Input) {
+        egui::TopBottomPanel::bottom("statusbar").show(ctx, |ui| {
+            self.load_progress(ui);
+            if let Some(project) = &self.project
+                && let Some(image) = project.images.get(project.image_index)
+            {
+                let problems = validation::problems(image, &project.labels);
+                if !problems.is_empty() {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("{} problems", problems.len()),
+                    )
+                    .on_hover_text(problems.join("\n"));
+                }
+            }
+            if let Some(project) = &self.project
+                && let Some(image) = project.images.get(project.image_index)
+                && let (Some(rect), Some(pixel_size)) = (project.view_rect, image.pixel_size)
+            {
+                let to_pixels = |v: egui::Vec2| v / rect.size() * pixel_size;
                 ui.horizontal(|ui| {
-                    for (i, image) in project.images.iter().enumerate().skip(skip_images) {
-                        if !clip_rect.contains(ui.next_widget_position()) {
-                            break;
-                        }
-
-                        let res = ui.add(
-                            egui::Image::new(&image.uri)
-                                .fit_to_exact_size(image_size)
-                                .sense(Sense::CLICK),
-                        );
-
-                        if res.clicked() {
-                            project.image_index = i;
-                        }
+                    ui.label(format!("{} × {} px", pixel_size.x, pixel_size.y));
+                    if let Some(hover_pos) = input.hover_pos
+                        && rect.contains(hover_pos)
+                    {
+                        let pos = to_pixels(hover_pos - rect.min);
+                        ui.separator();
+                        ui.label(format!("x: {:.0} y: {:.0}", pos.x, pos.y));
 
-                        if project.image_index == i {
-                            // let rect = res.rect.translate(egui::Vec2 { x: offset, y: 0.0 });
-                            ui.painter().debug_rect(res.rect, Color32::RED, "");
+                        let size = match (project.tool, project.drag_start_pos) {
+                            (Tool::Drag, Some(start)) => Some(to_pixels((hover_pos - start).abs())),
+                            (Tool::Stamp, _) if project.label_id.is_some() => {
+                                Some(project.rect_size * pixel_size)
+                            }
+                            _ => None,
+                        };
+                        if let Some(size) = size {
+                            ui.separator();
+                            ui.label(format!("box: {:.0} × {:.0} px", size.x, size.y));
                         }
                     }
                 });
@@ -533,16 +1250,34 @@ fn help(ui: &mut egui::Ui) {
     ui.label("A - Previous image");
     ui.label("D - Next image");
     ui.label("X - Delete segment");
+    ui.label("Ctrl+A / Esc - Select all / none");
+    ui.label("[Shift] Arrows - Nudge selection");
+    ui.label("Tab / [Alt] Scroll - Cycle overlapping");
     ui.label("C - Clone segment");
+    ui.label("1-9, 0 - Pick class");
+    ui.label("Ctrl+1-9, 0 - Reassign selected / hovered box");
+    ui.label("[Alt] 1-9, 0 on a label - Bind key");
+    ui.label("Ctrl+K - Find class, Ctrl+Enter to reassign");
+    ui.label("H - Hide annotations");
+    ui.label("G - Gallery / editor");
+    ui.label("Ctrl+F - Filter images");
+    ui.label("[Shift] N - Next unlabelled");
+    ui.label("[Shift] M - Next with selected class");
+    ui.label("[Shift] V - Next with problems");
+    ui.label("PgUp / PgDn - Jump");
+    ui.label("Ctrl+G - Go to file");
+    ui.label("B - Flag image");
+    ui.label("F1-F12 - Toggle image tag");
     ui.label("Click space to stamp");
     ui.label("[Shift] Scroll to resize stamp");
+    ui.label("[Shift/Ctrl] Click timeline - Pick images");
 }
 
 fn nav_buttons(ui: &mut egui::Ui, project: &mut Project) {
     ui.columns(5, |ui| {
         ui[0].vertical_centered_justified(|ui| {
             if ui.add(egui::Button::new("")).clicked() {
-                project.image_index = 0;
+                project.go_to_position(0);
             }
         });
         ui[1].vertical_centered_justified(|ui| {
@@ -550,10 +1285,16 @@ fn nav_buttons(ui: &mut egui::Ui, project: &mut Project) {
                 project.back();
             }
         });
-        ui[2].add(
-            egui::DragValue::new(&mut project.image_index)
-                .range(0..=(project.images.len().saturating_sub(1))),
-        );
+        let mut position = project.position().unwrap_or(0);
+        if ui[2]
+            .add(
+                egui::DragValue::new(&mut position)
+                    .range(0..=(project.order.len().saturating_sub(1))),
+            )
+            .changed()
+        {
+            project.go_to_position(position);
+        }
         ui[3].vertical_centered_justified(|ui| {
             if ui.add(egui::Button::new("")).clicked() {
                 project.advance();
@@ -561,12 +1302,155 @@ fn nav_buttons(ui: &mut egui::Ui, project: &mut Project) {
         });
         ui[4].vertical_centered_justified(|ui| {
             if ui.add(egui::Button::new("")).clicked() {
-                project.image_index = project.images.len().saturating_sub(1);
+                project.go_to_position(usize::MAX);
             }
         });
     });
+    egui::Grid::new("seek").num_columns(3).show(ui, |ui| {
+        for (seek, name) in [
+            (Seek::Unlabelled, "Unlabelled"),
+            (Seek::Class, "Class"),
+            (Seek::Problem, "Problem"),
+        ] {
+            ui.label(name);
+            if ui.button("").clicked() {
+                project.seek(seek, false);
+            }
+            if ui.button("").clicked() {
+                project.seek(seek, true);
+            }
+            ui.end_row();
+        }
+        ui.add(
+            egui::DragValue::new(&mut project.jump_size)
+                .range(1..=10000)
+                .prefix("Jump "),
+        );
+        let jump = project.jump_size as isize;
+        if ui.button("").clicked() {
+            project.jump(-jump);
+        }
+        if ui.button("").clicked() {
+            project.jump(jump);
+        }
+        ui.end_row();
+    });
+    let res = ui.add(
+        egui::TextEdit::singleline(&mut project.goto_query)
+            .id(egui::Id::new("goto"))
+            .hint_text("Go to file"),
+    );
+    if res.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+        let query = project.goto_query.clone();
+        project.go_to_name(&query);
+    }
+}
+
+fn loupe(
+    ctx: &egui::Context,
+    zoom: &mut f32,
+    texture: egui::load::SizedTexture,
+    image_rect: egui::Rect,
+    hover_pos: egui::Pos2,
+    drag_start_pos: Option<egui::Pos2>,
+    color: Color32,
+) {
+    egui::Window::new("Loupe")
+        .resizable(false)
+        .default_pos(image_rect.left_top())
+        .show(ctx, |ui| {
+            ui.add(egui::Slider::new(zoom, 1.0..=16.0).text("Zoom"));
+            let (rect, _) = ui.allocate_exact_size(egui::Vec2::splat(192.0), Sense::hover());
+            let scale = texture.size / image_rect.size() * *zoom;
+            let to_loupe = |pos: egui::Pos2| rect.center() + (pos - hover_pos) * scale;
+            let uv = egui::Rect::from_center_size(
+                ((hover_pos - image_rect.min) / image_rect.size()).to_pos2(),
+                rect.size() / *zoom / texture.size,
+            );
+
+            let painter = ui.painter_at(rect);
+            painter.rect_filled(rect, 0.0, Color32::BLACK);
+            painter.image(texture.id, rect, uv, Color32::WHITE);
+            painter.hline(rect.x_range(), rect.center().y, (1.0, color));
+            painter.vline(rect.center().x, rect.y_range(), (1.0, color));
+            if let Some(drag_start_pos) = drag_start_pos {
+                painter.rect_stroke(
+                    egui::Rect::from_two_pos(to_loupe(drag_start_pos), rect.center()),
+                    0.0,
+                    (1.0, color),
+                    egui::StrokeKind::Middle,
+                );
+            }
+        });
 }
 
+fn handles(rect: egui::Rect) -> impl Iterator<Item = egui::Rect> {
+    HANDLE_DIRECTIONS.iter().map(move |&dir| {
+        egui::Rect::from_center_size(
+            rect.center() + dir * rect.size() / 2.0,
+            egui::Vec2::splat(HANDLE_SIZE),
+        )
+    })
+}
+
+const HANDLE_DIRECTIONS: [egui::Vec2; 8] = [
+    egui::vec2(-1.0, -1.0),
+    egui::vec2(0.0, -1.0),
+    egui::vec2(1.0, -1.0),
+    egui::vec2(-1.0, 0.0),
+    egui::vec2(1.0, 0.0),
+    egui::vec2(-1.0, 1.0),
+    egui::vec2(0.0, 1.0),
+    egui::vec2(1.0, 1.0),
+];
+
+fn handle_at(rect: egui::Rect, pos: egui::Pos2) -> Option<egui::Vec2> {
+    HANDLE_DIRECTIONS
+        .iter()
+        .zip(handles(rect))
+        .find(|(_, handle)| handle.contains(pos))
+        .map(|(&dir, _)| dir)
+}
+
+fn handle_icon(dir: egui::Vec2) -> egui::CursorIcon {
+    match (dir.x as i32, dir.y as i32) {
+        (-1, -1) => egui::CursorIcon::ResizeNorthWest,
+        (0, -1) => egui::CursorIcon::ResizeNorth,
+        (1, -1) => egui::CursorIcon::ResizeNorthEast,
+        (-1, 0) => egui::CursorIcon::ResizeWest,
+        (1, 0) => egui::CursorIcon::ResizeEast,
+        (-1, 1) => egui::CursorIcon::ResizeSouthWest,
+        (0, 1) => egui::CursorIcon::ResizeSouth,
+        (1, 1) => egui::CursorIcon::ResizeSouthEast,
+        _ => egui::CursorIcon::Move,
+    }
+}
+
+fn snap(value: f32, targets: &[f32], tolerance: f32) -> f32 {
+    targets
+        .iter()
+        .copied()
+        .filter(|t| (t - value).abs() < tolerance)
+        .min_by(|a, b| (a - value).abs().total_cmp(&(b - value).abs()))
+        .unwrap_or(value)
+}
+
+/// Class hotkeys in keyboard order, so `1` is the first slot and `0` the last.
+pub const DIGIT_KEYS: [egui::Key; 10] = [
+    egui::Key::Num1,
+    egui::Key::Num2,
+    egui::Key::Num3,
+    egui::Key::Num4,
+    egui::Key::Num5,
+    egui::Key::Num6,
+    egui::Key::Num7,
+    egui::Key::Num8,
+    egui::Key::Num9,
+    egui::Key::Num0,
+];
+
+const DIGIT_NAMES: [&str; 10] = ["1", "2", "3", "4", "5", "6", "7", "8", "9", "0"];
+
 pub fn fun_name(image_rect: egui::Rect, segment: &Segment) -> egui::Rect {
     egui::Rect::from_center_size(
         image_rect.min
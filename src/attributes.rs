@@ -0,0 +1,276 @@
+use crate::app::App;
+use crate::project::Segment;
+
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub enum AttributeKind {
+    Bool,
+    Choice(Vec<String>),
+    Text,
+}
+
+impl AttributeKind {
+    fn name(&self) -> &'static str {
+        match self {
+            AttributeKind::Bool => "Yes / no",
+            AttributeKind::Choice(_) => "Choice",
+            AttributeKind::Text => "Text",
+        }
+    }
+
+    fn default_value(&self) -> AttributeValue {
+        match self {
+            AttributeKind::Bool => AttributeValue::Bool(false),
+            AttributeKind::Choice(choices) => {
+                AttributeValue::Text(choices.first().cloned().unwrap_or_default())
+            }
+            AttributeKind::Text => AttributeValue::Text(String::new()),
+        }
+    }
+}
+
+/// An attribute boxes of a class can have, such as "occluded".
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AttributeSpec {
+    pub name: String,
+    pub kind: AttributeKind,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Bool(bool),
+    Text(String),
+}
+
+impl std::fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeValue::Bool(value) => write!(f, "{}", value),
+            AttributeValue::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+pub type Attributes = BTreeMap<String, AttributeValue>;
+
+/// Attributes of a box as exported. Yes / no and choice attributes that were
+/// never set get their default, so every box of a class has them.
+pub fn with_defaults(attributes: &Attributes, schema: Option<&Vec<AttributeSpec>>) -> Attributes {
+    let mut attributes = attributes.clone();
+    for spec in schema.into_iter().flatten() {
+        if !matches!(spec.kind, AttributeKind::Text) {
+            attributes
+                .entry(spec.name.clone())
+                .or_insert_with(|| spec.kind.default_value());
+        }
+    }
+    attributes
+}
+
+/// Label files only hold boxes, so attributes are kept in a json file next
+/// to them with one entry per line of the label file.
+pub fn path(labels_file_path: &Path) -> PathBuf {
+    labels_file_path.with_extension("attributes.json")
+}
+
+/// Attributes of each box, empty if the image has none.
+pub fn read(labels_file_path: &Path) -> anyhow::Result<Vec<Attributes>> {
+    match fs::read_to_string(path(labels_file_path)) {
+        Ok(contents) => Ok(serde_json::from_str(&contents)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Writes the attributes of `segments`, removing the file once none are left.
+pub fn write(labels_file_path: &Path, segments: &[Segment]) -> anyhow::Result<()> {
+    let path = path(labels_file_path);
+    if segments.iter().all(|segment| segment.attributes.is_empty()) {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    let attributes: Vec<&Attributes> = segments.iter().map(|s| &s.attributes).collect();
+    fs::write(path, serde_json::to_string_pretty(&attributes)?)?;
+    Ok(())
+}
+
+/// Input fields for adding an attribute to a class schema.
+#[derive(Default)]
+pub struct SchemaEditor {
+    name: String,
+    kind: usize,
+    /// Comma separated choices.
+    choices: String,
+}
+
+enum SchemaAction {
+    Add(AttributeSpec),
+    Remove(usize),
+}
+
+impl App {
+    /// Attributes of the selected boxes, and the schema of their class.
+    pub fn attribute_window(&mut self, ctx: &egui::Context) {
+        let Some(project) = &mut self.project else {
+            return;
+        };
+        let hidden_labels: Vec<u32> = project
+            .labels
+            .keys()
+            .copied()
+            .filter(|&id| !project.label_visible(id))
+            .collect();
+        let Some(image) = project.images.get_mut(project.image_index) else {
+            return;
+        };
+        let mut selected = image
+            .segments
+            .iter_mut()
+            .filter(|s| s.selected && !s.hidden && !hidden_labels.contains(&s.label_id))
+            .peekable();
+        let class = selected.peek().map(|s| s.label_id);
+        let class_name = class
+            .and_then(|id| project.labels.get(&id))
+            .map_or("-", |l| &l.name);
+        let schema = class
+            .and_then(|id| project.attribute_schema.get(&id))
+            .cloned()
+            .unwrap_or_default();
+
+        let editor = &mut self.schema_editor;
+        let mut action = None;
+        egui::Window::new("Attributes")
+            .open(&mut self.show_attributes)
+            .show(ctx, |ui| {
+                let Some(class) = class else {
+                    ui.label("Select a box to edit its attributes.");
+                    return;
+                };
+                // Edits go to every selected box of the class.
+                let mut segments: Vec<&mut Segment> =
+                    selected.filter(|s| s.label_id == class).collect();
+                ui.label(format!("{} box(es) of {}", segments.len(), class_name));
+
+                egui::Grid::new("attributes").num_columns(3).show(ui, |ui| {
+                    for (i, spec) in schema.iter().enumerate() {
+                        ui.label(&spec.name);
+                        let mut value = segments[0]
+                            .attributes
+                            .get(&spec.name)
+                            .cloned()
+                            .unwrap_or_else(|| spec.kind.default_value());
+                        let changed = match (&spec.kind, &mut value) {
+                            (AttributeKind::Bool, AttributeValue::Bool(value)) => {
+                                ui.checkbox(value, "").changed()
+                            }
+                            (AttributeKind::Choice(choices), AttributeValue::Text(value)) => {
+                                let mut changed = false;
+                                egui::ComboBox::from_id_salt(("attribute", i))
+                                    .selected_text(value.as_str())
+                                    .show_ui(ui, |ui| {
+                                        for choice in choices {
+                                            changed |= ui
+                                                .selectable_value(value, choice.clone(), choice)
+                                                .changed();
+                                        }
+                                    });
+                                changed
+                            }
+                            (_, AttributeValue::Text(value)) => {
+                                ui.text_edit_singleline(value).changed()
+                            }
+                            // A value stored before the schema changed.
+                            (_, value) => {
+                                ui.label(value.to_string());
+                                false
+                            }
+                        };
+                        if changed {
+                            for segment in &mut segments {
+                                segment.attributes.insert(spec.name.clone(), value.clone());
+                            }
+                        }
+                        if ui
+                            .small_button("×")
+                            .on_hover_text("Remove from schema")
+                            .clicked()
+                        {
+                            action = Some(SchemaAction::Remove(i));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut editor.name)
+                            .hint_text("Attribute")
+                            .desired_width(100.0),
+                    );
+                    let kinds = [
+                        AttributeKind::Bool,
+                        AttributeKind::Choice(vec![]),
+                        AttributeKind::Text,
+                    ];
+                    egui::ComboBox::from_id_salt("attributekind")
+                        .selected_text(kinds[editor.kind].name())
+                        .show_ui(ui, |ui| {
+                            for (i, kind) in kinds.iter().enumerate() {
+                                ui.selectable_value(&mut editor.kind, i, kind.name());
+                            }
+                        });
+                    if editor.kind == 1 {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut editor.choices)
+                                .hint_text("red, green, blue")
+                                .desired_width(100.0),
+                        );
+                    }
+                    if ui.button("Add").clicked() && !editor.name.is_empty() {
+                        let kind = match editor.kind {
+                            0 => AttributeKind::Bool,
+                            1 => AttributeKind::Choice(
+                                editor
+                                    .choices
+                                    .split(',')
+                                    .map(|c| c.trim().to_string())
+                                    .filter(|c| !c.is_empty())
+                                    .collect(),
+                            ),
+                            _ => AttributeKind::Text,
+                        };
+                        action = Some(SchemaAction::Add(AttributeSpec {
+                            name: std::mem::take(&mut editor.name),
+                            kind,
+                        }));
+                    }
+                });
+            });
+
+        if let Some(class) = class {
+            let schema = project.attribute_schema.entry(class).or_default();
+            match action {
+                Some(SchemaAction::Add(spec)) if schema.iter().all(|s| s.name != spec.name) => {
+                    schema.push(spec);
+                }
+                Some(SchemaAction::Remove(i)) => {
+                    schema.remove(i);
+                }
+                _ => {}
+            }
+            if schema.is_empty() {
+                project.attribute_schema.remove(&class);
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::app::App;
+use crate::project::Project;
+
+use egui_extras::Column;
+
+/// Flags and note of the current image, shown under its file name.
+pub fn image_notes(ui: &mut egui::Ui, project: &mut Project) {
+    let flags = project.flags();
+    let Some(image) = project.images.get_mut(project.image_index) else {
+        return;
+    };
+    ui.horizontal_wrapped(|ui| {
+        for flag in flags {
+            let mut set = image.flags.contains(&flag);
+            if ui.toggle_value(&mut set, &flag).changed() {
+                if set {
+                    image.flags.insert(flag);
+                } else {
+                    image.flags.remove(&flag);
+                }
+            }
+        }
+    });
+    ui.add(
+        egui::TextEdit::multiline(&mut image.note)
+            .hint_text("Note")
+            .desired_rows(2),
+    );
+}
+
+impl App {
+    /// Every flagged or annotated image, for jumping back to them.
+    pub fn bookmarks_window(&mut self, ctx: &egui::Context) {
+        let Some(project) = &mut self.project else {
+            return;
+        };
+
+        let mut jump = None;
+        egui::Window::new("Bookmarks")
+            .open(&mut self.show_bookmarks)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("B flags as");
+                    ui.text_edit_singleline(&mut project.flag);
+                });
+                ui.add(
+                    egui::TextEdit::singleline(&mut project.bookmark_search)
+                        .hint_text("Search names, flags and notes"),
+                );
+
+                let query = project.bookmark_search.to_lowercase();
+                let rows: Vec<usize> = project
+                    .images
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, image)| !image.flags.is_empty() || !image.note.is_empty())
+                    .filter(|(_, image)| {
+                        image.file_name.to_lowercase().contains(&query)
+                            || image.note.to_lowercase().contains(&query)
+                            || image
+                                .flags
+                                .iter()
+                                .any(|flag| flag.to_lowercase().contains(&query))
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
+
+                egui_extras::TableBuilder::new(ui)
+                    .striped(true)
+                    .sense(egui::Sense::click())
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .column(Column::remainder())
+                    .header(18.0, |mut header| {
+                        header.col(|ui| {
+                            ui.strong("Image");
+                        });
+                        header.col(|ui| {
+                            ui.strong("Flags");
+                        });
+                        header.col(|ui| {
+                            ui.strong("Note");
+                        });
+                    })
+                    .body(|body| {
+                        body.rows(20.0, rows.len(), |mut row| {
+                            let i = rows[row.index()];
+                            let image = &project.images[i];
+                            row.set_selected(i == project.image_index);
+                            row.col(|ui| {
+                                ui.label(&image.file_name);
+                            });
+                            row.col(|ui| {
+                                let flags: Vec<&str> =
+                                    image.flags.iter().map(String::as_str).collect();
+                                ui.label(flags.join(", "));
+                            });
+                            row.col(|ui| {
+                                ui.label(image.note.lines().next().unwrap_or_default())
+                                    .on_hover_text(&image.note);
+                            });
+                            if row.response().clicked() {
+                                jump = Some(i);
+                            }
+                        });
+                    });
+            });
+
+        if let Some(i) = jump {
+            project.image_index = i;
+        }
+    }
+}
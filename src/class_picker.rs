@@ -0,0 +1,92 @@
+use crate::app::App;
+
+impl App {
+    pub fn quick_picker(&mut self, ctx: &egui::Context) {
+        let (Some(project), Some(query)) = (&mut self.project, &mut self.quick_picker) else {
+            return;
+        };
+
+        let mut matches: Vec<(usize, u32)> = project
+            .labels
+            .iter()
+            .filter_map(|(&id, label)| fuzzy_score(query, &label.name).map(|score| (score, id)))
+            .collect();
+        matches.sort();
+
+        let mut picked = None;
+        let relabel = ctx.input(|r| r.modifiers.command);
+        let mut close = false;
+        egui::Modal::new("quickpicker".into()).show(ctx, |ui| {
+            ui.set_width(240.0);
+            let res = ui.add(egui::TextEdit::singleline(query).hint_text("Class"));
+            res.request_focus();
+            if ui.input(|r| r.key_pressed(egui::Key::Enter)) {
+                picked = matches.first().map(|m| m.1);
+            }
+            if ui.input(|r| r.key_pressed(egui::Key::Escape)) {
+                close = true;
+            }
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(320.0)
+                .show(ui, |ui| {
+                    for (_, id) in &matches {
+                        let label = &project.labels[id];
+                        let text = egui::RichText::new(&label.name).color(label.color);
+                        if ui.selectable_label(false, text).clicked() {
+                            picked = Some(*id);
+                        }
+                    }
+                });
+        });
+
+        if let Some(id) = picked {
+            project.assign_label(id, relabel, None);
+            close = true;
+        }
+        if close {
+            self.quick_picker = None;
+        }
+    }
+}
+
+/// Scores `name` as a case-insensitive subsequence match of `query`, lower is
+/// better. Returns `None` if some character of the query is missing.
+fn fuzzy_score(query: &str, name: &str) -> Option<usize> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    for c in query.to_lowercase().chars() {
+        let found = name[pos..].iter().position(|&n| n == c)?;
+        score += found;
+        pos += found + 1;
+    }
+    Some(score + name.len() - pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_needs_every_character_in_order() {
+        assert!(fuzzy_score("car", "Car").is_some());
+        assert!(fuzzy_score("cr", "car").is_some());
+        assert!(fuzzy_score("rc", "car").is_none());
+        assert!(fuzzy_score("cars", "car").is_none());
+        assert!(fuzzy_score("", "car").is_some());
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_short_names() {
+        assert_eq!(fuzzy_score("car", "car"), Some(0));
+        assert!(fuzzy_score("car", "car") < fuzzy_score("car", "cargo"));
+        assert!(fuzzy_score("car", "cargo") < fuzzy_score("car", "cat bear"));
+    }
+
+    #[test]
+    fn fuzzy_score_ignores_case_of_multibyte_names() {
+        assert_eq!(fuzzy_score("ÉCOLE", "école"), Some(0));
+        assert!(fuzzy_score("zu", "Straße zu").is_some());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::attributes;
+use crate::attributes::Attributes;
+use crate::project::Project;
+
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+#[derive(serde::Serialize)]
+pub struct CocoJson {
+    pub info: Value,
+    pub licenses: Value,
+    pub images: Vec<CocoImage>,
+    pub annotations: Vec<CocoAnnotation>,
+    pub categories: Vec<CocoCategory>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CocoImage {
+    id: u32,
+    width: u32,
+    height: u32,
+    file_name: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct CocoAnnotation {
+    id: u32,
+    image_id: u32,
+    category_id: u32,
+    /// Left, top, width and height in pixels.
+    bbox: [f32; 4],
+    area: f32,
+    iscrowd: u8,
+    #[serde(skip_serializing_if = "Attributes::is_empty")]
+    attributes: Attributes,
+}
+
+#[derive(serde::Serialize)]
+pub struct CocoCategory {
+    id: u32,
+    name: String,
+}
+
+impl From<&Project> for CocoJson {
+    /// Images whose labels or size couldn't be read are left out. Boxes are
+    /// in the same frame as the label files.
+    fn from(project: &Project) -> Self {
+        let mut images = vec![];
+        let mut annotations = vec![];
+        for image in &project.images {
+            let (true, Some(size)) = (image.labels_loaded, image.pixel_size) else {
+                continue;
+            };
+            let policy = project.orientation_policy;
+            let size = policy.stored_size(image.orientation, size);
+            let image_id = images.len() as u32 + 1;
+            images.push(CocoImage {
+                id: image_id,
+                width: size.x as u32,
+                height: size.y as u32,
+                file_name: image.file_name.clone(),
+            });
+            for segment in &image.segments {
+                let (center, box_size) =
+                    policy.stored_box(image.orientation, segment.center, segment.size);
+                let min = ((center - box_size / 2.0).to_vec2() * size).max(egui::Vec2::ZERO);
+                let max = ((center + box_size / 2.0).to_vec2() * size).min(size);
+                let extent = (max - min).max(egui::Vec2::ZERO);
+                annotations.push(CocoAnnotation {
+                    id: annotations.len() as u32 + 1,
+                    image_id,
+                    category_id: segment.label_id,
+                    bbox: [min.x, min.y, extent.x, extent.y],
+                    area: extent.x * extent.y,
+                    iscrowd: 0,
+                    attributes: attributes::with_defaults(
+                        &segment.attributes,
+                        project.attribute_schema.get(&segment.label_id),
+                    ),
+                });
+            }
+        }
+
+        let mut categories: Vec<CocoCategory> = project
+            .labels
+            .iter()
+            .map(|(&id, label)| CocoCategory {
+                id,
+                name: label.name.clone(),
+            })
+            .collect();
+        categories.sort_by_key(|category| category.id);
+
+        CocoJson {
+            info: Value::Object(Default::default()),
+            licenses: Value::Array(vec![]),
+            images,
+            annotations,
+            categories,
+        }
+    }
+}
+
+/// Returns the number of images exported.
+pub fn export(project: &Project, path: &Path) -> anyhow::Result<usize> {
+    let coco = CocoJson::from(project);
+    fs::write(path, serde_json::to_string_pretty(&coco)?)?;
+    Ok(coco.images.len())
+}
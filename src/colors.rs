@@ -1,13 +1,60 @@
 use egui::Color32;
 
-pub(crate) const COLORS: &[Color32] = &[
-    Color32::from_rgb(255, 0, 0),
-    Color32::from_rgb(0, 255, 0),
-    Color32::from_rgb(0, 0, 255),
-    Color32::from_rgb(255, 255, 0),
-    Color32::from_rgb(255, 0, 255),
-    Color32::from_rgb(0, 255, 255),
-    Color32::from_rgb(255, 128, 0),
-    Color32::from_rgb(128, 0, 255),
-    Color32::from_rgb(0, 255, 128),
+/// Okabe-Ito palette, distinguishable with the common color vision deficiencies.
+const COLOR_BLIND_SAFE: &[Color32] = &[
+    Color32::from_rgb(230, 159, 0),
+    Color32::from_rgb(86, 180, 233),
+    Color32::from_rgb(0, 158, 115),
+    Color32::from_rgb(240, 228, 66),
+    Color32::from_rgb(0, 114, 178),
+    Color32::from_rgb(213, 94, 0),
+    Color32::from_rgb(204, 121, 167),
+    Color32::from_rgb(255, 255, 255),
 ];
+
+#[derive(PartialEq, Eq, Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub enum Palette {
+    #[default]
+    Distinct,
+    ColorBlindSafe,
+}
+
+impl Palette {
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Distinct => "Distinct",
+            Palette::ColorBlindSafe => "Color-blind safe",
+        }
+    }
+
+    pub fn color(self, index: u32) -> Color32 {
+        match self {
+            Palette::Distinct => distinct(index),
+            Palette::ColorBlindSafe => match COLOR_BLIND_SAFE.get(index as usize) {
+                Some(&color) => color,
+                // Past the end there is no safe choice left, fall back to generated hues.
+                None => distinct(index),
+            },
+        }
+    }
+}
+
+/// Walks the OKLCH hue circle by the golden angle, so consecutive classes are
+/// far apart and no hue ever repeats. Lightness alternates to
+/// separate hues that end up close together.
+fn distinct(index: u32) -> Color32 {
+    let hue = (index as f32 * 137.507_77).to_radians();
+    let lightness = [0.75, 0.6, 0.88][index as usize % 3];
+    oklch(lightness, 0.15, hue)
+}
+
+fn oklch(l: f32, c: f32, h: f32) -> Color32 {
+    let (a, b) = (c * h.cos(), c * h.sin());
+    let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    let r = 4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_;
+    let g = -1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_;
+    let b = -0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_;
+    egui::Rgba::from_rgb(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0)).into()
+}
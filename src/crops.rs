@@ -0,0 +1,359 @@
+use crate::app::App;
+use crate::app::View;
+use crate::gallery::TileGrid;
+use crate::label_manager::label_combo;
+use crate::project::Project;
+use crate::project::Segment;
+use crate::texture_cache::TextureCache;
+
+use egui::Color32;
+use image::GenericImageView;
+use image::metadata::Orientation;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Bounds of a crop, large enough for the biggest tiles.
+const CROP_SIZE: u32 = 256;
+/// Context shown around a box, as a fraction of its size.
+const MARGIN: f32 = 0.15;
+/// Upper bound on images being cropped at once.
+const MAX_PENDING: usize = 8;
+
+/// A box is identified by its image, the orientation it's shown in and its
+/// exact extent, so a crop is redone when the box is moved.
+type CropKey = (PathBuf, Orientation, [u32; 4]);
+/// A cropped image and where the box lies in it, in 0..1 coordinates.
+type Crop = (egui::ColorImage, egui::Rect);
+type CropTexture = (egui::TextureHandle, egui::Rect);
+/// Image and segment index of every box of a class, and the class and sort
+/// they were gathered for.
+pub type ClassBoxes = ((u32, CropSort), Vec<(usize, usize)>);
+
+#[derive(Default, PartialEq, Eq, Copy, Clone)]
+pub enum CropSort {
+    #[default]
+    Image,
+    Size,
+    Aspect,
+}
+
+impl CropSort {
+    fn name(self) -> &'static str {
+        match self {
+            CropSort::Image => "Image",
+            CropSort::Size => "Size",
+            CropSort::Aspect => "Aspect",
+        }
+    }
+}
+
+fn crop_key(path: &Path, orientation: Orientation, segment: &Segment) -> CropKey {
+    let bits = [
+        segment.center.x.to_bits(),
+        segment.center.y.to_bits(),
+        segment.size.x.to_bits(),
+        segment.size.y.to_bits(),
+    ];
+    (path.to_path_buf(), orientation, bits)
+}
+
+/// Crops of boxes for the crop gallery, cut on the thread pool with one
+/// decode per image and only kept as textures while in view.
+#[derive(Default)]
+pub struct CropCache {
+    cache: TextureCache<CropKey, CropTexture, PathBuf, Vec<(CropKey, Option<Crop>)>>,
+    /// Crops asked for this frame that aren't being cut yet, by image.
+    wanted: HashMap<PathBuf, (Orientation, Vec<(CropKey, egui::Rect)>)>,
+}
+
+impl CropCache {
+    /// Uploads the crops finished since the last frame.
+    pub fn receive(&mut self, ctx: &egui::Context) {
+        self.cache.receive(|_, crops| {
+            crops.into_iter().map(|(key, crop)| {
+                let texture = crop.map(|(image, inner)| {
+                    let name = format!("{}#{:?}", key.0.display(), key.1);
+                    (
+                        ctx.load_texture(name, image, egui::TextureOptions::LINEAR),
+                        inner,
+                    )
+                });
+                (key, texture)
+            })
+        });
+    }
+
+    /// The crop of `segment`, queueing it up if it isn't ready yet.
+    pub fn get(
+        &mut self,
+        path: &Path,
+        orientation: Orientation,
+        segment: &Segment,
+    ) -> Option<&CropTexture> {
+        let key = crop_key(path, orientation, segment);
+        if !self.cache.contains(&key) && !self.cache.is_pending(path) {
+            let rect = egui::Rect::from_center_size(segment.center, segment.size);
+            self.wanted
+                .entry(path.to_path_buf())
+                .or_insert((orientation, vec![]))
+                .1
+                .push((key.clone(), rect));
+        }
+        self.cache.get(&key)
+    }
+
+    /// Starts cutting the crops asked for this frame.
+    pub fn request(&mut self, ctx: &egui::Context) {
+        for (path, (orientation, boxes)) in self.wanted.drain() {
+            if self.cache.pending() >= MAX_PENDING {
+                break;
+            }
+            self.cache
+                .spawn(ctx, path, move |path| match image::open(path) {
+                    Ok(mut image) => {
+                        image.apply_orientation(orientation);
+                        boxes
+                            .into_iter()
+                            .map(|(key, rect)| (key, Some(crop(&image, rect))))
+                            .collect()
+                    }
+                    Err(err) => {
+                        println!("{}: {}", path.display(), err);
+                        boxes.into_iter().map(|(key, _)| (key, None)).collect()
+                    }
+                });
+        }
+    }
+
+    /// Drops the textures of crops that weren't asked for this frame.
+    pub fn evict(&mut self) {
+        self.cache.evict();
+        self.wanted.clear();
+    }
+}
+
+/// Cuts `rect`, in 0..1 image coordinates, out of `image` with some margin.
+fn crop(image: &image::DynamicImage, rect: egui::Rect) -> Crop {
+    let size = egui::vec2(image.width() as f32, image.height() as f32);
+    let outer = rect
+        .expand2(rect.size() * MARGIN)
+        .intersect(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::Vec2::splat(1.0),
+        ));
+    let min = (outer.min.to_vec2() * size)
+        .floor()
+        .min(size - egui::Vec2::splat(1.0));
+    let max = (outer.max.to_vec2() * size)
+        .ceil()
+        .max(min + egui::Vec2::splat(1.0));
+    let pixels = image
+        .view(
+            min.x as u32,
+            min.y as u32,
+            (max - min).x as u32,
+            (max - min).y as u32,
+        )
+        .to_image();
+    let pixels = image::DynamicImage::ImageRgba8(pixels).thumbnail(CROP_SIZE, CROP_SIZE);
+    let rgba = pixels.to_rgba8();
+
+    let extent = (max - min) / size;
+    let inner = egui::Rect::from_min_max(
+        ((rect.min.to_vec2() - min / size) / extent).to_pos2(),
+        ((rect.max.to_vec2() - min / size) / extent).to_pos2(),
+    );
+    let image = egui::ColorImage::from_rgba_unmultiplied(
+        [rgba.width() as usize, rgba.height() as usize],
+        rgba.as_raw(),
+    );
+    (image, inner)
+}
+
+/// Image and segment index of every box of `class`, in `sort` order.
+fn class_boxes(project: &Project, class: u32, sort: CropSort) -> Vec<(usize, usize)> {
+    let mut boxes: Vec<(usize, usize)> = project
+        .images
+        .iter()
+        .enumerate()
+        .flat_map(|(i, image)| {
+            image
+                .segments
+                .iter()
+                .enumerate()
+                .filter(|(_, segment)| segment.label_id == class)
+                .map(move |(j, _)| (i, j))
+        })
+        .collect();
+    // Relative to the image, as not every image's size is known.
+    let size = |&(i, j): &(usize, usize)| project.images[i].segments[j].size;
+    match sort {
+        CropSort::Image => {}
+        CropSort::Size => boxes.sort_by(|a, b| {
+            let area = |size: egui::Vec2| size.x * size.y;
+            area(size(a)).total_cmp(&area(size(b)))
+        }),
+        CropSort::Aspect => boxes.sort_by(|a, b| {
+            let aspect = |size: egui::Vec2| size.x / size.y;
+            aspect(size(a)).total_cmp(&aspect(size(b)))
+        }),
+    }
+    boxes
+}
+
+enum CropAction {
+    Open(usize, usize),
+    Reassign(usize, usize, u32),
+    Delete(usize, usize),
+}
+
+impl App {
+    /// Every box of one class across the dataset as cropped tiles, for
+    /// spotting boxes that don't belong.
+    pub fn crop_gallery(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let Some(project) = &mut self.project else {
+                return;
+            };
+            let mut label_ids: Vec<u32> = project.labels.keys().copied().collect();
+            label_ids.sort();
+            if self
+                .crop_class
+                .is_none_or(|id| !project.labels.contains_key(&id))
+            {
+                self.crop_class = project.label_id.or(label_ids.first().copied());
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Class");
+                label_combo(
+                    ui,
+                    "cropclass",
+                    &project.labels,
+                    &label_ids,
+                    &mut self.crop_class,
+                );
+                egui::ComboBox::from_label("Sort")
+                    .selected_text(self.crop_sort.name())
+                    .show_ui(ui, |ui| {
+                        for sort in [CropSort::Image, CropSort::Size, CropSort::Aspect] {
+                            ui.selectable_value(&mut self.crop_sort, sort, sort.name());
+                        }
+                    });
+                ui.add(
+                    egui::Slider::new(&mut self.gallery_tile_size, 64.0..=256.0).text("Tile size"),
+                );
+            });
+
+            let Some(class) = self.crop_class else {
+                return;
+            };
+            let key = (class, self.crop_sort);
+            if project
+                .crop_boxes
+                .as_ref()
+                .is_none_or(|(cached, _)| *cached != key)
+            {
+                project.crop_boxes = Some((key, class_boxes(project, class, self.crop_sort)));
+            }
+            let Some((_, boxes)) = &project.crop_boxes else {
+                return;
+            };
+            ui.label(format!("{} boxes", boxes.len()));
+
+            let color = project
+                .labels
+                .get(&class)
+                .map_or(Color32::WHITE, |l| l.color);
+            let mut action = None;
+            TileGrid::new(ui, self.gallery_tile_size).show(
+                ui,
+                egui::ScrollArea::vertical().auto_shrink(false),
+                boxes,
+                |ui, slot, res, &(i, j)| {
+                    let Some(image) = project.images.get(i) else {
+                        return;
+                    };
+                    let Some(segment) = image.segments.get(j) else {
+                        return;
+                    };
+                    let painter = ui.painter_at(slot);
+                    match self.crops.get(&image.file_path, image.orientation, segment) {
+                        Some((texture, inner)) => {
+                            let size = texture.size_vec2();
+                            let rect = egui::Rect::from_center_size(
+                                slot.center(),
+                                size * (slot.size() / size).min_elem(),
+                            );
+                            painter.image(
+                                texture.id(),
+                                rect,
+                                egui::Rect::from_min_max(
+                                    egui::pos2(0.0, 0.0),
+                                    egui::pos2(1.0, 1.0),
+                                ),
+                                Color32::WHITE,
+                            );
+                            let inner = egui::Rect::from_min_max(
+                                rect.lerp_inside(inner.min.to_vec2()),
+                                rect.lerp_inside(inner.max.to_vec2()),
+                            );
+                            painter.rect_stroke(
+                                inner,
+                                0.0,
+                                (1.0, color),
+                                egui::StrokeKind::Outside,
+                            );
+                        }
+                        None => {
+                            painter.rect_filled(slot, 0.0, ui.visuals().faint_bg_color);
+                        }
+                    }
+
+                    let res = res.on_hover_text(format!("{} #{}", image.file_name, j));
+                    if res.clicked() {
+                        action = Some(CropAction::Open(i, j));
+                    }
+                    res.context_menu(|ui| {
+                        ui.menu_button("Reassign to", |ui| {
+                            for &id in &label_ids {
+                                let name = project.labels.get(&id).map_or("?", |l| &l.name);
+                                if id != class && ui.button(name).clicked() {
+                                    action = Some(CropAction::Reassign(i, j, id));
+                                }
+                            }
+                        });
+                        if ui.button("Delete").clicked() {
+                            action = Some(CropAction::Delete(i, j));
+                        }
+                    });
+                },
+            );
+            self.crops.request(ctx);
+
+            match action {
+                Some(CropAction::Open(i, j)) => {
+                    project.go_to(i);
+                    for (k, segment) in project.images[i].segments.iter_mut().enumerate() {
+                        segment.selected = k == j;
+                    }
+                    self.view = View::Editor;
+                }
+                Some(CropAction::Reassign(i, j, id)) => {
+                    project.images[i].segments[j].set_label(id, &project.attribute_schema);
+                    project.other_label_counts = None;
+                    project.crop_boxes = None;
+                }
+                Some(CropAction::Delete(i, j)) => {
+                    project.images[i].segments.remove(j);
+                    project.hover_candidates.clear();
+                    project.edit_drag = None;
+                    project.other_label_counts = None;
+                    project.crop_boxes = None;
+                }
+                None => {}
+            }
+        });
+    }
+}
@@ -0,0 +1,27 @@
+/// Quotes a CSV field if it contains a separator, quote or newline.
+pub fn field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields_are_kept() {
+        assert_eq!(field("car"), "car");
+        assert_eq!(field(""), "");
+        assert_eq!(field("traffic light 2"), "traffic light 2");
+    }
+
+    #[test]
+    fn special_fields_are_quoted() {
+        assert_eq!(field("car, red"), "\"car, red\"");
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("two\nlines"), "\"two\nlines\"");
+    }
+}
@@ -0,0 +1,530 @@
+use crate::project::Image;
+
+use image::DynamicImage;
+use image::metadata::Orientation;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum Channel {
+    All,
+    Gray,
+    Red,
+    Green,
+    Blue,
+}
+
+impl Channel {
+    fn name(self) -> &'static str {
+        match self {
+            Channel::All => "RGB",
+            Channel::Gray => "Gray",
+            Channel::Red => "Red",
+            Channel::Green => "Green",
+            Channel::Blue => "Blue",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum Colormap {
+    None,
+    Inferno,
+    Viridis,
+    Turbo,
+}
+
+impl Colormap {
+    fn name(self) -> &'static str {
+        match self {
+            Colormap::None => "None",
+            Colormap::Inferno => "Inferno",
+            Colormap::Viridis => "Viridis",
+            Colormap::Turbo => "Turbo",
+        }
+    }
+
+    /// Polynomial fits of the matplotlib and Google colormaps.
+    fn color(self, t: f32) -> [u8; 3] {
+        let poly = |c: [[f32; 3]; 7]| -> [f32; 3] {
+            std::array::from_fn(|i| c.iter().rev().fold(0.0, |acc, c| acc * t + c[i]))
+        };
+        let rgb = match self {
+            Colormap::None => [t; 3],
+            Colormap::Inferno => poly([
+                [0.000_218_94, 0.001_651, -0.019_480_9],
+                [0.106_513_42, 0.563_956_4, 3.932_712_4],
+                [11.602_493, -3.972_854, -15.942_394],
+                [-41.703_996, 17.436_4, 44.354_145],
+                [77.162_94, -33.402_36, -81.807_31],
+                [-71.319_43, 32.626_064, 73.209_52],
+                [25.131_126, -12.242_669, -23.070_324],
+            ]),
+            Colormap::Viridis => poly([
+                [0.277_727_33, 0.005_407_344_5, 0.334_099_8],
+                [0.105_093_04, 1.404_613_5, 1.384_590_2],
+                [-0.330_861_83, 0.214_847_56, 0.095_095_16],
+                [-4.634_230_6, -5.799_101, -19.332_441],
+                [6.228_27, 14.179_933, 56.690_55],
+                [4.776_385, -13.745_145, -65.353_03],
+                [-5.435_456, 4.645_852_6, 26.312_435],
+            ]),
+            Colormap::Turbo => poly([
+                [0.135_721_38, 0.091_402_61, 0.106_673_3],
+                [4.615_392_6, 2.194_188_4, 12.641_946],
+                [-42.660_324, 4.842_966_6, -60.582_05],
+                [132.131_08, -14.185_033, 110.362_77],
+                [-152.942_4, 4.277_299, -89.903_11],
+                [59.286_38, 2.829_566, 27.348_25],
+                [0.0, 0.0, 0.0],
+            ]),
+        };
+        rgb.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+}
+
+/// Adjustments applied to the central image when it's drawn. They never
+/// touch the files on disk.
+#[derive(PartialEq, Clone)]
+pub struct DisplayFilter {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub gamma: f32,
+    pub auto_levels: bool,
+    /// Contrast limited adaptive histogram equalization of the luminance.
+    pub equalize: bool,
+    pub channel: Channel,
+    /// Maps the luminance to a false color scale.
+    pub colormap: Colormap,
+    /// Range of a high bit depth image mapped to the displayed 8 bits, as
+    /// center and width in 0..1. Automatic picks it from the histogram.
+    pub auto_window: bool,
+    pub window_level: f32,
+    pub window_width: f32,
+}
+
+impl Default for DisplayFilter {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            auto_levels: false,
+            equalize: false,
+            channel: Channel::All,
+            colormap: Colormap::None,
+            auto_window: true,
+            window_level: 0.5,
+            window_width: 1.0,
+        }
+    }
+}
+
+impl DisplayFilter {
+    pub fn is_identity(&self) -> bool {
+        *self == DisplayFilter::default()
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.add(egui::Slider::new(&mut self.brightness, -1.0..=1.0).text("Brightness"));
+        ui.add(egui::Slider::new(&mut self.contrast, 0.0..=4.0).text("Contrast"));
+        ui.add(
+            egui::Slider::new(&mut self.gamma, 0.2..=5.0)
+                .logarithmic(true)
+                .text("Gamma"),
+        );
+        ui.checkbox(&mut self.auto_levels, "Auto levels");
+        ui.checkbox(&mut self.equalize, "Equalize (CLAHE)");
+        ui.horizontal(|ui| {
+            for channel in [
+                Channel::All,
+                Channel::Gray,
+                Channel::Red,
+                Channel::Green,
+                Channel::Blue,
+            ] {
+                ui.selectable_value(&mut self.channel, channel, channel.name());
+            }
+        });
+        egui::ComboBox::from_label("Colormap")
+            .selected_text(self.colormap.name())
+            .show_ui(ui, |ui| {
+                for colormap in [
+                    Colormap::None,
+                    Colormap::Inferno,
+                    Colormap::Viridis,
+                    Colormap::Turbo,
+                ] {
+                    ui.selectable_value(&mut self.colormap, colormap, colormap.name());
+                }
+            });
+        ui.label("Window (16-bit images)");
+        ui.checkbox(&mut self.auto_window, "Automatic");
+        ui.add_enabled_ui(!self.auto_window, |ui| {
+            ui.add(egui::Slider::new(&mut self.window_level, 0.0..=1.0).text("Level"));
+            ui.add(
+                egui::Slider::new(&mut self.window_width, 0.001..=1.0)
+                    .logarithmic(true)
+                    .text("Width"),
+            );
+        });
+        if ui.button("Reset").clicked() {
+            *self = DisplayFilter::default();
+        }
+    }
+
+    fn apply(&self, source: &DynamicImage, auto_window: (f32, f32)) -> egui::ColorImage {
+        let mut pixels: Vec<[u8; 4]> = if is_high_bit_depth(source.color()) {
+            let (low, high) = if self.auto_window {
+                auto_window
+            } else {
+                (
+                    self.window_level - self.window_width / 2.0,
+                    self.window_level + self.window_width / 2.0,
+                )
+            };
+            source
+                .to_rgba32f()
+                .as_raw()
+                .par_chunks_exact(4)
+                .map(|p| {
+                    let window = |v: f32| ((v - low) / (high - low)).clamp(0.0, 1.0);
+                    [window(p[0]), window(p[1]), window(p[2]), p[3]]
+                        .map(|v| (v * 255.0).round() as u8)
+                })
+                .collect()
+        } else {
+            source.to_rgba8().pixels().map(|p| p.0).collect()
+        };
+
+        if self.channel != Channel::All {
+            pixels.par_iter_mut().for_each(|p| {
+                let v = match self.channel {
+                    Channel::Red => p[0],
+                    Channel::Green => p[1],
+                    Channel::Blue => p[2],
+                    _ => luma(p),
+                };
+                *p = [v, v, v, p[3]];
+            });
+        }
+
+        if self.auto_levels {
+            let luts = auto_levels(&pixels);
+            apply_luts(&mut pixels, &luts);
+        }
+
+        if self.equalize {
+            clahe(
+                &mut pixels,
+                source.width() as usize,
+                source.height() as usize,
+            );
+        }
+
+        let lut: [u8; 256] = std::array::from_fn(|v| {
+            let v = v as f32 / 255.0;
+            let v = ((v - 0.5) * self.contrast + 0.5 + self.brightness).clamp(0.0, 1.0);
+            (v.powf(1.0 / self.gamma) * 255.0).round() as u8
+        });
+        apply_luts(&mut pixels, &[lut; 3]);
+
+        if self.colormap != Colormap::None {
+            let colors: [[u8; 3]; 256] =
+                std::array::from_fn(|v| self.colormap.color(v as f32 / 255.0));
+            pixels.par_iter_mut().for_each(|p| {
+                let [r, g, b] = colors[luma(p) as usize];
+                *p = [r, g, b, p[3]];
+            });
+        }
+
+        egui::ColorImage::from_rgba_unmultiplied(
+            [source.width() as usize, source.height() as usize],
+            pixels.as_flattened(),
+        )
+    }
+}
+
+/// Whether the default 8-bit loader would lose most of the image's range.
+pub fn is_high_bit_depth(color: image::ColorType) -> bool {
+    color.bytes_per_pixel() / color.channel_count() > 1
+}
+
+/// Reads just the header of an image to check its bit depth.
+pub fn probe_high_bit_depth(path: &Path) -> bool {
+    image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .ok()
+        .and_then(|reader| reader.into_decoder().ok())
+        .is_some_and(|decoder| is_high_bit_depth(image::ImageDecoder::color_type(&decoder)))
+}
+
+/// The 0.5th and 99.5th percentiles of the luminance, sampled.
+fn auto_window(image: &DynamicImage) -> (f32, f32) {
+    let luma = image.to_luma32f();
+    let step = (luma.len() / 100_000).max(1);
+    let mut values: Vec<f32> = luma.as_raw().iter().step_by(step).copied().collect();
+    if values.is_empty() {
+        return (0.0, 1.0);
+    }
+    values.sort_by(f32::total_cmp);
+    let low = values[values.len() / 200];
+    let high = values[values.len() - 1 - values.len() / 200];
+    (low, high.max(low + f32::EPSILON))
+}
+
+/// Whether `image` has to be decoded by [`DisplayCache`] rather than egui's
+/// loader, which ignores EXIF orientation and squashes high bit depths.
+pub fn needs_decoder(image: &Image, filter: &DisplayFilter) -> bool {
+    !filter.is_identity()
+        || image.high_bit_depth == Some(true)
+        || image.orientation != Orientation::NoTransforms
+}
+
+/// An image file and the orientation it's shown in, which isn't known until
+/// its labels are read.
+type SourceKey = (PathBuf, Orientation);
+/// A decoded image and the window [`auto_window`] picked for it.
+type Source = (Arc<DynamicImage>, (f32, f32));
+/// Images decoded on the thread pool, `None` while still decoding.
+type Prefetched = Arc<Mutex<HashMap<SourceKey, Option<Result<Source, String>>>>>;
+/// What a texture shows.
+type TextureKey = (SourceKey, DisplayFilter);
+/// Filtered pixels, filled in by the thread pool.
+type Filtered = Arc<Mutex<Option<egui::ColorImage>>>;
+
+/// The filtered texture of the central image. Decoding and filtering both
+/// happen on the thread pool, and the texture is rebuilt when either the image
+/// or the filter changes.
+#[derive(Default)]
+pub struct DisplayCache {
+    texture: Option<(TextureKey, egui::TextureHandle)>,
+    /// At most one image is filtered at a time, so dragging a slider doesn't
+    /// pile up work. The latest filter is picked up once it's done.
+    filtering: Option<(TextureKey, Filtered)>,
+    prefetched: Prefetched,
+    /// Images egui's loader was last asked to keep around.
+    resident_uris: Vec<String>,
+}
+
+impl DisplayCache {
+    /// Starts decoding `images` in the background and frees every image
+    /// decoded earlier that isn't among them.
+    pub fn prefetch(&mut self, ctx: &egui::Context, images: &[&Image], filter: &DisplayFilter) {
+        let uris: Vec<String> = images
+            .iter()
+            .filter(|image| !needs_decoder(image, filter))
+            .map(|image| image.uri.clone())
+            .collect();
+        for uri in &self.resident_uris {
+            if !uris.contains(uri) {
+                ctx.forget_image(uri);
+            }
+        }
+        for uri in &uris {
+            // egui's loader decodes on its own thread.
+            let _ = ctx.try_load_image(uri, egui::SizeHint::default());
+        }
+        self.resident_uris = uris;
+
+        let decoded: Vec<&Image> = images
+            .iter()
+            .copied()
+            .filter(|image| needs_decoder(image, filter))
+            .collect();
+        let mut prefetched = self.prefetched.lock().unwrap();
+        prefetched.retain(|key, _| {
+            decoded
+                .iter()
+                .any(|image| (&image.file_path, image.orientation) == (&key.0, key.1))
+        });
+        for image in decoded {
+            let key = (image.file_path.clone(), image.orientation);
+            if prefetched.contains_key(&key) {
+                continue;
+            }
+            prefetched.insert(key.clone(), None);
+            let (path, orientation) = key.clone();
+            let prefetched = self.prefetched.clone();
+            let ctx = ctx.clone();
+            rayon::spawn(move || {
+                let source = image::open(&path)
+                    .map(|mut image| {
+                        image.apply_orientation(orientation);
+                        // Only high bit depths are windowed.
+                        let window = if is_high_bit_depth(image.color()) {
+                            auto_window(&image)
+                        } else {
+                            (0.0, 1.0)
+                        };
+                        (Arc::new(image), window)
+                    })
+                    .map_err(|err| err.to_string());
+                // Dropped if the user moved on in the meantime.
+                if let Some(slot) = prefetched.lock().unwrap().get_mut(&key) {
+                    *slot = Some(source);
+                    ctx.request_repaint();
+                }
+            });
+        }
+    }
+
+    /// The filtered texture of `path`, or `None` while it's still being
+    /// decoded or filtered for the first time. While the filter changes the
+    /// previous texture is kept.
+    pub fn texture(
+        &mut self,
+        ctx: &egui::Context,
+        path: &Path,
+        orientation: Orientation,
+        filter: &DisplayFilter,
+    ) -> anyhow::Result<Option<egui::load::SizedTexture>> {
+        let source_key = (path.to_path_buf(), orientation);
+        let key = (source_key.clone(), filter.clone());
+
+        let pixels = self
+            .filtering
+            .as_ref()
+            .and_then(|(_, filtered)| filtered.lock().unwrap().take());
+        if let Some(pixels) = pixels
+            && let Some((done, _)) = self.filtering.take()
+        {
+            let texture = ctx.load_texture("display", pixels, egui::TextureOptions::LINEAR);
+            self.texture = Some((done, texture));
+        }
+
+        if self.filtering.is_none() && self.texture.as_ref().is_none_or(|(k, _)| *k != key) {
+            let prefetched = self
+                .prefetched
+                .lock()
+                .unwrap()
+                .get(&source_key)
+                .cloned()
+                .flatten();
+            if let Some(source) = prefetched {
+                let (image, window) = source.map_err(anyhow::Error::msg)?;
+                let filtered = Filtered::default();
+                self.filtering = Some((key.clone(), filtered.clone()));
+                let filter = filter.clone();
+                let ctx = ctx.clone();
+                rayon::spawn(move || {
+                    let pixels = filter.apply(&image, window);
+                    *filtered.lock().unwrap() = Some(pixels);
+                    ctx.request_repaint();
+                });
+            }
+        }
+
+        Ok(self
+            .texture
+            .as_ref()
+            .filter(|((shown, _), _)| *shown == source_key)
+            .map(|(_, texture)| egui::load::SizedTexture::from_handle(texture)))
+    }
+}
+
+fn luma(p: &[u8; 4]) -> u8 {
+    (0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32) as u8
+}
+
+fn apply_luts(pixels: &mut [[u8; 4]], luts: &[[u8; 256]; 3]) {
+    pixels.par_iter_mut().for_each(|p| {
+        for c in 0..3 {
+            p[c] = luts[c][p[c] as usize];
+        }
+    });
+}
+
+/// Stretches each channel so its 0.5th and 99.5th percentiles span the full range.
+fn auto_levels(pixels: &[[u8; 4]]) -> [[u8; 256]; 3] {
+    std::array::from_fn(|c| {
+        let mut histogram = [0usize; 256];
+        for p in pixels {
+            histogram[p[c] as usize] += 1;
+        }
+        let clip = pixels.len() / 200;
+        let percentile = |mut bins: Box<dyn Iterator<Item = usize>>| {
+            let mut sum = 0;
+            bins.find(|&v| {
+                sum += histogram[v];
+                sum > clip
+            })
+            .unwrap_or(0)
+        };
+        let low = percentile(Box::new(0..256));
+        let high = percentile(Box::new((0..256).rev())).max(low + 1);
+        std::array::from_fn(|v| {
+            let v = (v as f32 - low as f32) / (high - low) as f32;
+            (v.clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+    })
+}
+
+const CLAHE_TILES: usize = 8;
+const CLAHE_CLIP: f32 = 2.0;
+
+/// Equalizes the luminance histogram of each tile of an 8×8 grid, clipping
+/// it to limit noise amplification, and blends neighbouring tiles' mappings
+/// bilinearly. Colors are scaled to follow the new luminance.
+fn clahe(pixels: &mut [[u8; 4]], width: usize, height: usize) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    let tile_w = width.div_ceil(CLAHE_TILES);
+    let tile_h = height.div_ceil(CLAHE_TILES);
+    let luts: Vec<[f32; 256]> = (0..CLAHE_TILES * CLAHE_TILES)
+        .into_par_iter()
+        .map(|tile| {
+            let (tx, ty) = (tile % CLAHE_TILES, tile / CLAHE_TILES);
+            let mut histogram = [0f32; 256];
+            let mut count = 0.0;
+            for y in (ty * tile_h)..((ty + 1) * tile_h).min(height) {
+                for x in (tx * tile_w)..((tx + 1) * tile_w).min(width) {
+                    histogram[luma(&pixels[y * width + x]) as usize] += 1.0;
+                    count += 1.0;
+                }
+            }
+            let limit = CLAHE_CLIP * count / 256.0;
+            let mut excess = 0.0;
+            for bin in &mut histogram {
+                excess += (*bin - limit).max(0.0);
+                *bin = bin.min(limit);
+            }
+            let mut lut = [0.0; 256];
+            let mut sum = 0.0;
+            for (v, bin) in histogram.iter().enumerate() {
+                sum += bin + excess / 256.0;
+                lut[v] = sum / count.max(1.0) * 255.0;
+            }
+            lut
+        })
+        .collect();
+
+    pixels
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let fy = ((y as f32 + 0.5) / tile_h as f32 - 0.5).clamp(0.0, (CLAHE_TILES - 1) as f32);
+            let (y0, ay) = (fy.floor() as usize, fy.fract());
+            let y1 = (y0 + 1).min(CLAHE_TILES - 1);
+            for (x, p) in row.iter_mut().enumerate() {
+                let fx =
+                    ((x as f32 + 0.5) / tile_w as f32 - 0.5).clamp(0.0, (CLAHE_TILES - 1) as f32);
+                let (x0, ax) = (fx.floor() as usize, fx.fract());
+                let x1 = (x0 + 1).min(CLAHE_TILES - 1);
+                let l = luma(p) as usize;
+                let lut = |tx: usize, ty: usize| luts[ty * CLAHE_TILES + tx][l];
+                let top = lut(x0, y0) * (1.0 - ax) + lut(x1, y0) * ax;
+                let bottom = lut(x0, y1) * (1.0 - ax) + lut(x1, y1) * ax;
+                let new = top * (1.0 - ay) + bottom * ay;
+                let scale = new / (l as f32).max(1.0);
+                for v in &mut p[..3] {
+                    *v = (*v as f32 * scale).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        });
+}
@@ -0,0 +1,348 @@
+use crate::app::App;
+use crate::project::Image;
+use crate::project::ImageStatus;
+
+use crate::thumbnails::fnv1a;
+
+use regex::Regex;
+use std::cmp::Ordering;
+
+#[derive(Default, PartialEq, Eq, Copy, Clone)]
+pub enum ImageSort {
+    /// Plain file name order, as the images are loaded.
+    #[default]
+    Name,
+    /// File name order with numbers compared by value.
+    Natural,
+    Modified,
+    BoxCount,
+    /// Shuffled, the same way for the same seed on every machine.
+    Random,
+}
+
+impl ImageSort {
+    fn name(self) -> &'static str {
+        match self {
+            ImageSort::Name => "Name",
+            ImageSort::Natural => "Natural",
+            ImageSort::Modified => "Modified",
+            ImageSort::BoxCount => "Box count",
+            ImageSort::Random => "Random",
+        }
+    }
+}
+
+/// Which images navigation, the timeline and the gallery go through, and in
+/// what order.
+#[derive(Default, Clone)]
+pub struct ImageFilter {
+    /// Glob on the file name, or a regex if `regex` is set. Text without
+    /// wildcards matches anywhere in the name.
+    pub name: String,
+    pub regex: bool,
+    pub class: Option<u32>,
+    pub no_labels: bool,
+    /// Inclusive range of the number of boxes.
+    pub boxes: Option<(usize, usize)>,
+    pub status: Option<ImageStatus>,
+    /// Image tag, as group and tag name.
+    pub tag: Option<(String, String)>,
+    pub sort: ImageSort,
+    pub seed: u64,
+}
+
+impl ImageFilter {
+    pub fn is_active(&self) -> bool {
+        !self.name.is_empty()
+            || self.class.is_some()
+            || self.no_labels
+            || self.boxes.is_some()
+            || self.status.is_some()
+            || self.tag.is_some()
+    }
+
+    fn name_regex(&self) -> Result<Option<Regex>, regex::Error> {
+        if self.name.is_empty() {
+            return Ok(None);
+        }
+        let pattern = if self.regex {
+            self.name.clone()
+        } else if self.name.contains(['*', '?']) {
+            let mut pattern = "(?i)^".to_string();
+            for c in self.name.chars() {
+                match c {
+                    '*' => pattern.push_str(".*"),
+                    '?' => pattern.push('.'),
+                    c => pattern.push_str(&regex::escape(&c.to_string())),
+                }
+            }
+            pattern.push('$');
+            pattern
+        } else {
+            format!("(?i){}", regex::escape(&self.name))
+        };
+        Regex::new(&pattern).map(Some)
+    }
+
+    fn matches(&self, image: &Image, name: Option<&Regex>) -> bool {
+        name.is_none_or(|name| name.is_match(&image.file_name))
+            && self
+                .class
+                .is_none_or(|class| image.segments.iter().any(|s| s.label_id == class))
+            && (!self.no_labels || image.segments.is_empty())
+            && self
+                .boxes
+                .is_none_or(|(min, max)| (min..=max).contains(&image.segments.len()))
+            && self.status.is_none_or(|status| image.status() == status)
+            && self.tag.as_ref().is_none_or(|(group, tag)| {
+                image.tags.get(group).is_some_and(|tags| tags.contains(tag))
+            })
+    }
+
+    /// Indices of the images that pass the filter, in sort order.
+    pub fn apply(&self, images: &[Image]) -> Result<Vec<usize>, regex::Error> {
+        let name = self.name_regex()?;
+        let mut order: Vec<usize> = (0..images.len())
+            .filter(|&i| self.matches(&images[i], name.as_ref()))
+            .collect();
+        match self.sort {
+            ImageSort::Name => {}
+            ImageSort::Natural => {
+                order.sort_by(|&a, &b| natural_cmp(&images[a].file_name, &images[b].file_name))
+            }
+            ImageSort::Modified => order.sort_by_key(|&i| images[i].modified),
+            ImageSort::BoxCount => order.sort_by_key(|&i| images[i].segments.len()),
+            ImageSort::Random => order.sort_by_cached_key(|&i| {
+                let mut key = self.seed.to_le_bytes().to_vec();
+                key.extend(images[i].file_name.as_bytes());
+                fnv1a(&key)
+            }),
+        }
+        Ok(order)
+    }
+}
+
+/// Compares names with runs of digits compared by value, so `img2` comes
+/// before `img10`. Names that only differ in leading zeros fall back to plain
+/// order.
+pub fn natural_cmp(name_a: &str, name_b: &str) -> Ordering {
+    let (mut a, mut b) = (name_a, name_b);
+    loop {
+        match (a.chars().next(), b.chars().next()) {
+            (None, None) => return name_a.cmp(name_b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let end_a = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+                let end_b = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+                let digits_a = a[..end_a].trim_start_matches('0');
+                let digits_b = b[..end_b].trim_start_matches('0');
+                let ordering = digits_a
+                    .len()
+                    .cmp(&digits_b.len())
+                    .then_with(|| digits_a.cmp(digits_b));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                (a, b) = (&a[end_a..], &b[end_b..]);
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                (a, b) = (&a[x.len_utf8()..], &b[y.len_utf8()..]);
+            }
+        }
+    }
+}
+
+impl App {
+    pub fn filter_bar(&mut self, ctx: &egui::Context) {
+        if !self.show_filter_bar {
+            return;
+        }
+        let Some(project) = &mut self.project else {
+            return;
+        };
+        // Going to a hidden file clears the filter, and its error with it.
+        if !project.filter.is_active() {
+            self.filter_error = None;
+        }
+        let mut label_ids: Vec<u32> = project.labels.keys().copied().collect();
+        label_ids.sort();
+
+        egui::TopBottomPanel::top("filterbar").show(ctx, |ui| {
+            let filter = &mut project.filter;
+            let mut changed = false;
+            ui.horizontal_wrapped(|ui| {
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut filter.name)
+                            .hint_text("File name")
+                            .desired_width(160.0),
+                    )
+                    .changed();
+                changed |= ui.checkbox(&mut filter.regex, "Regex").changed();
+                ui.separator();
+
+                let class_name = filter
+                    .class
+                    .and_then(|id| project.labels.get(&id))
+                    .map_or("Any class", |l| &l.name);
+                egui::ComboBox::from_id_salt("filterclass")
+                    .selected_text(class_name)
+                    .show_ui(ui, |ui| {
+                        changed |= ui
+                            .selectable_value(&mut filter.class, None, "Any class")
+                            .changed();
+                        for &id in &label_ids {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut filter.class,
+                                    Some(id),
+                                    project.labels.get(&id).map_or("?", |l| &l.name),
+                                )
+                                .changed();
+                        }
+                    });
+                changed |= ui.checkbox(&mut filter.no_labels, "No labels").changed();
+
+                let mut limit_boxes = filter.boxes.is_some();
+                changed |= ui.checkbox(&mut limit_boxes, "Boxes").changed();
+                let (mut min, mut max) = filter.boxes.unwrap_or((0, 10));
+                ui.add_enabled_ui(limit_boxes, |ui| {
+                    changed |= ui.add(egui::DragValue::new(&mut min)).changed();
+                    ui.label("to");
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut max).range(min..=usize::MAX))
+                        .changed();
+                });
+                filter.boxes = limit_boxes.then_some((min, max.max(min)));
+
+                let status_name = filter.status.map_or("Any status", ImageStatus::name);
+                egui::ComboBox::from_id_salt("filterstatus")
+                    .selected_text(status_name)
+                    .show_ui(ui, |ui| {
+                        changed |= ui
+                            .selectable_value(&mut filter.status, None, "Any status")
+                            .changed();
+                        for status in [
+                            ImageStatus::Unlabelled,
+                            ImageStatus::Background,
+                            ImageStatus::Labelled,
+                        ] {
+                            changed |= ui
+                                .selectable_value(&mut filter.status, Some(status), status.name())
+                                .changed();
+                        }
+                    });
+
+                if !project.tag_groups.is_empty() {
+                    let tag_name = filter
+                        .tag
+                        .as_ref()
+                        .map_or("Any tag".to_string(), |(group, tag)| {
+                            format!("{}: {}", group, tag)
+                        });
+                    egui::ComboBox::from_id_salt("filtertag")
+                        .selected_text(tag_name)
+                        .show_ui(ui, |ui| {
+                            changed |= ui
+                                .selectable_value(&mut filter.tag, None, "Any tag")
+                                .changed();
+                            for group in &project.tag_groups {
+                                for tag in &group.tags {
+                                    let value = Some((group.name.clone(), tag.clone()));
+                                    changed |= ui
+                                        .selectable_value(
+                                            &mut filter.tag,
+                                            value,
+                                            format!("{}: {}", group.name, tag),
+                                        )
+                                        .changed();
+                                }
+                            }
+                        });
+                }
+                ui.separator();
+
+                egui::ComboBox::from_label("Sort")
+                    .selected_text(filter.sort.name())
+                    .show_ui(ui, |ui| {
+                        for sort in [
+                            ImageSort::Name,
+                            ImageSort::Natural,
+                            ImageSort::Modified,
+                            ImageSort::BoxCount,
+                            ImageSort::Random,
+                        ] {
+                            changed |= ui
+                                .selectable_value(&mut filter.sort, sort, sort.name())
+                                .changed();
+                        }
+                    });
+                if filter.sort == ImageSort::Random {
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut filter.seed).prefix("Seed "))
+                        .changed();
+                }
+                ui.separator();
+
+                if ui
+                    .button("Refresh")
+                    .on_hover_text("Apply the filter to the current labels")
+                    .clicked()
+                {
+                    changed = true;
+                }
+                if ui.button("Clear").clicked() {
+                    *filter = ImageFilter::default();
+                    changed = true;
+                }
+                ui.label(format!(
+                    "{} / {} images",
+                    project.order.len(),
+                    project.images.len()
+                ));
+                if let Some(err) = &self.filter_error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                }
+            });
+
+            if changed {
+                self.filter_error = project.refresh_order().err().map(|err| err.to_string());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural_cmp_compares_numbers_by_value() {
+        assert_eq!(natural_cmp("img2.png", "img10.png"), Ordering::Less);
+        assert_eq!(natural_cmp("img10.png", "img2.png"), Ordering::Greater);
+        assert_eq!(natural_cmp("img10.png", "img10.png"), Ordering::Equal);
+        assert_eq!(natural_cmp("a9b", "a10a"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_cmp_orders_leading_zeros_and_prefixes() {
+        // Equal values fall back to plain order, so `01` sorts before `1`.
+        assert_eq!(natural_cmp("img01", "img1"), Ordering::Less);
+        assert_eq!(natural_cmp("img1", "img01"), Ordering::Greater);
+        assert_eq!(natural_cmp("img001", "img01"), Ordering::Less);
+        assert_eq!(natural_cmp("img", "img1"), Ordering::Less);
+        assert_eq!(natural_cmp("b", "a10"), Ordering::Greater);
+        assert_eq!(natural_cmp("ä2", "ä10"), Ordering::Less);
+    }
+
+    #[test]
+    fn natural_cmp_sorts_a_list() {
+        let mut names = vec!["img10", "img2", "img1", "img", "cat3", "img02"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, ["cat3", "img", "img1", "img02", "img2", "img10"]);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::app::App;
+use crate::app::View;
+use crate::timeline::thumbnail_tile;
+
+use egui::Sense;
+
+/// Rows of square tiles filling the available width, of which only the rows
+/// in view are laid out.
+pub struct TileGrid {
+    tile: egui::Vec2,
+    columns: usize,
+}
+
+impl TileGrid {
+    pub fn new(ui: &egui::Ui, tile_size: f32) -> Self {
+        let spacing = ui.spacing().item_spacing;
+        let tile = egui::Vec2::splat(tile_size);
+        let columns = ((ui.available_width() + spacing.x) / (tile.x + spacing.x))
+            .floor()
+            .max(1.0) as usize;
+        Self { tile, columns }
+    }
+
+    /// Scroll offset that brings the tile at `position` to the top.
+    pub fn offset(&self, ui: &egui::Ui, position: usize) -> f32 {
+        (position / self.columns) as f32 * (self.tile.y + ui.spacing().item_spacing.y)
+    }
+
+    /// Calls `add_tile` with the slot and response of every tile in view.
+    pub fn show<T>(
+        &self,
+        ui: &mut egui::Ui,
+        scroll: egui::ScrollArea,
+        items: &[T],
+        mut add_tile: impl FnMut(&mut egui::Ui, egui::Rect, egui::Response, &T),
+    ) {
+        let rows = items.len().div_ceil(self.columns);
+        scroll.show_rows(ui, self.tile.y, rows, |ui, row_range| {
+            for row in row_range {
+                ui.horizontal(|ui| {
+                    let end = ((row + 1) * self.columns).min(items.len());
+                    for item in &items[row * self.columns..end] {
+                        let (slot, res) = ui.allocate_exact_size(self.tile, Sense::CLICK);
+                        add_tile(ui, slot, res, item);
+                    }
+                });
+            }
+        });
+    }
+}
+
+impl App {
+    /// Grid of every image for skimming, virtualized by rows. Double-click
+    /// opens an image in the editor.
+    pub fn gallery(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let Some(project) = &mut self.project else {
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::Slider::new(&mut self.gallery_tile_size, 64.0..=256.0).text("Tile size"),
+                );
+                ui.separator();
+                ui.label(format!(
+                    "{} images, {} picked",
+                    project.order.len(),
+                    project.picked_images.len()
+                ));
+            });
+
+            let grid = TileGrid::new(ui, self.gallery_tile_size);
+            let mut scroll = egui::ScrollArea::vertical().auto_shrink(false);
+            // Bring the current image into view when it was changed elsewhere.
+            if self.gallery_follow != Some(project.image_index)
+                && let Some(position) = project.position()
+            {
+                self.gallery_follow = Some(project.image_index);
+                scroll = scroll.vertical_scroll_offset(grid.offset(ui, position));
+            }
+
+            let mut clicked = None;
+            let mut opened = None;
+            grid.show(ui, scroll, &project.order, |ui, slot, res, &i| {
+                let res = res.on_hover_text(&project.images[i].file_name);
+                thumbnail_tile(ui, &mut self.thumbnails, project, i, slot);
+                if res.double_clicked() {
+                    opened = Some(i);
+                } else if res.clicked() {
+                    clicked = Some(i);
+                }
+            });
+
+            if let Some(i) = clicked
+                && !project.pick_image(i, ui.input(|i| i.modifiers))
+            {
+                project.picked_images.clear();
+                project.picked_images.insert(i);
+                project.pick_anchor = Some(i);
+            }
+            if let Some(i) = opened {
+                project.image_index = i;
+                self.gallery_follow = Some(i);
+                self.view = View::Editor;
+            }
+        });
+    }
+}
@@ -0,0 +1,186 @@
+use crate::app::App;
+use crate::project::Project;
+
+enum LabelAction {
+    Swap(u32, u32),
+    Delete(u32),
+    Merge(u32, u32),
+    Compact,
+}
+
+impl App {
+    pub fn label_manager(&mut self, ctx: &egui::Context) {
+        let Some(project) = &mut self.project else {
+            return;
+        };
+        let loading = self.loader.is_some();
+
+        let mut label_ids: Vec<u32> = project.labels.keys().copied().collect();
+        label_ids.sort();
+
+        let mut action = None;
+        egui::Window::new("Classes")
+            .open(&mut self.show_label_manager)
+            .show(ctx, |ui| {
+                if loading {
+                    ui.label("Classes can be edited once loading has finished.");
+                    ui.disable();
+                }
+                egui::Grid::new("labelmanager")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (n, &id) in label_ids.iter().enumerate() {
+                            ui.label(id.to_string());
+                            let label = project.labels.get_mut(&id).unwrap();
+                            ui.add(
+                                egui::TextEdit::singleline(&mut label.name).desired_width(140.0),
+                            );
+                            ui.add_enabled_ui(n > 0, |ui| {
+                                if ui.small_button("").clicked() {
+                                    action = Some(LabelAction::Swap(id, label_ids[n - 1]));
+                                }
+                            });
+                            ui.add_enabled_ui(n + 1 < label_ids.len(), |ui| {
+                                if ui.small_button("").clicked() {
+                                    action = Some(LabelAction::Swap(id, label_ids[n + 1]));
+                                }
+                            });
+                            if ui.small_button("").on_hover_text("Delete").clicked() {
+                                action = Some(LabelAction::Delete(id));
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let (from, into) = &mut project.merge_labels;
+                    label_combo(ui, "mergefrom", &project.labels, &label_ids, from);
+                    ui.label("into");
+                    label_combo(ui, "mergeinto", &project.labels, &label_ids, into);
+                    if let (Some(from), Some(into)) = (*from, *into)
+                        && from != into
+                        && ui.button("Merge").clicked()
+                    {
+                        action = Some(LabelAction::Merge(from, into));
+                    }
+                });
+                if ui
+                    .button("Renumber")
+                    .on_hover_text("Renumber class ids to 0, 1, 2...")
+                    .clicked()
+                {
+                    action = Some(LabelAction::Compact);
+                }
+            });
+
+        let result = match action {
+            Some(LabelAction::Swap(a, b)) => project.swap_label_ids(a, b),
+            Some(LabelAction::Delete(id)) => {
+                project.delete_label_modal = Some((id, None));
+                Ok(())
+            }
+            Some(LabelAction::Merge(from, into)) => {
+                project.merge_labels = (None, None);
+                if project.labels.contains_key(&from) && project.labels.contains_key(&into) {
+                    project.delete_label(from, Some(into))
+                } else {
+                    Ok(())
+                }
+            }
+            Some(LabelAction::Compact) => project.compact_label_ids(),
+            None => Ok(()),
+        };
+        if let Err(err) = result {
+            self.message_box = Some(format!("{}", err));
+        }
+
+        self.delete_label_modal(ctx);
+    }
+
+    fn delete_label_modal(&mut self, ctx: &egui::Context) {
+        let Some(project) = &mut self.project else {
+            return;
+        };
+        let Some((id, mut reassign)) = project.delete_label_modal else {
+            return;
+        };
+
+        let mut label_ids: Vec<u32> = project
+            .labels
+            .keys()
+            .copied()
+            .filter(|&l| l != id)
+            .collect();
+        label_ids.sort();
+        let count = box_count(project, id);
+
+        let clicked = egui::Modal::new("deletelabel".into())
+            .show(ctx, |ui| {
+                ui.vertical_centered_justified(|ui| {
+                    ui.label(format!(
+                        "Delete \"{}\" and its {} boxes?",
+                        project.labels.get(&id).map_or("?", |l| &l.name),
+                        count
+                    ));
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut reassign, None, "Drop boxes");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("or move them to");
+                        label_combo(ui, "reassign", &project.labels, &label_ids, &mut reassign);
+                    });
+                    ui.columns(2, |uis| {
+                        if uis[0].button("Delete").clicked() {
+                            Some(true)
+                        } else if uis[1].button("Cancel").clicked() {
+                            Some(false)
+                        } else {
+                            None
+                        }
+                    })
+                })
+                .inner
+            })
+            .inner;
+
+        match clicked {
+            Some(true) => {
+                project.delete_label_modal = None;
+                if let Err(err) = project.delete_label(id, reassign) {
+                    self.message_box = Some(format!("{}", err));
+                }
+            }
+            Some(false) => project.delete_label_modal = None,
+            None => project.delete_label_modal = Some((id, reassign)),
+        }
+    }
+}
+
+pub const MANAGE_ICON: &str = "";
+
+fn box_count(project: &Project, label_id: u32) -> usize {
+    project
+        .images
+        .iter()
+        .flat_map(|image| &image.segments)
+        .filter(|segment| segment.label_id == label_id)
+        .count()
+}
+
+pub fn label_combo(
+    ui: &mut egui::Ui,
+    id_salt: &str,
+    labels: &egui::ahash::HashMap<u32, crate::project::Label>,
+    label_ids: &[u32],
+    value: &mut Option<u32>,
+) {
+    let name = |id: Option<u32>| id.and_then(|id| labels.get(&id)).map_or("-", |l| &l.name);
+    egui::ComboBox::from_id_salt(id_salt)
+        .selected_text(name(*value))
+        .show_ui(ui, |ui| {
+            for &id in label_ids {
+                ui.selectable_value(value, Some(id), name(Some(id)));
+            }
+        });
+}
@@ -0,0 +1,197 @@
+use crate::app::App;
+use crate::project;
+use crate::project::ImageLabels;
+use crate::project::Project;
+
+use rayon::prelude::*;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::thread;
+
+/// Counters shared with the loading thread.
+#[derive(Default)]
+pub struct LoadProgress {
+    /// Directory entries looked at so far.
+    pub scanned: AtomicUsize,
+    /// Images whose label file has been read.
+    pub parsed: AtomicUsize,
+    pub cancel: AtomicBool,
+}
+
+enum LoadEvent {
+    /// The image list is known, labels follow.
+    Listed(Box<Project>),
+    Labels(usize, anyhow::Result<ImageLabels>),
+    Failed(anyhow::Error),
+    Done,
+}
+
+/// Opens a project on a worker thread. The project is handed over as soon as
+/// its images are listed and label files are parsed in parallel after that.
+pub struct ProjectLoader {
+    progress: Arc<LoadProgress>,
+    receiver: mpsc::Receiver<LoadEvent>,
+    /// Number of images, once listed.
+    total: Option<usize>,
+    errors: Vec<String>,
+    /// Images whose box attributes were dropped.
+    warnings: Vec<String>,
+}
+
+impl ProjectLoader {
+    pub fn spawn(yaml_file_path: PathBuf) -> ProjectLoader {
+        let progress = Arc::new(LoadProgress::default());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn({
+            let progress = progress.clone();
+            move || {
+                let project = match Project::open(yaml_file_path, &progress) {
+                    Ok(project) => project,
+                    Err(err) => {
+                        let _ = sender.send(LoadEvent::Failed(err));
+                        return;
+                    }
+                };
+                let policy = project.orientation_policy;
+                let files: Vec<_> = project
+                    .images
+                    .iter()
+                    .map(|image| (image.file_path.clone(), image.labels_file_path.clone()))
+                    .collect();
+                let _ = sender.send(LoadEvent::Listed(Box::new(project)));
+
+                files.into_par_iter().enumerate().for_each_with(
+                    sender.clone(),
+                    |sender, (index, (file_path, labels_file_path))| {
+                        if progress.cancel.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        let labels = project::read_labels(&file_path, &labels_file_path, policy)
+                            .map_err(|err| err.context(labels_file_path.display().to_string()));
+                        progress.parsed.fetch_add(1, Ordering::Relaxed);
+                        let _ = sender.send(LoadEvent::Labels(index, labels));
+                    },
+                );
+                let _ = sender.send(LoadEvent::Done);
+            }
+        });
+        ProjectLoader {
+            progress,
+            receiver,
+            total: None,
+            errors: vec![],
+            warnings: vec![],
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.progress.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl App {
+    pub fn open_project(&mut self, yaml_file_path: PathBuf) {
+        if let Some(loader) = &self.loader {
+            loader.cancel();
+        }
+        self.loader = Some(ProjectLoader::spawn(yaml_file_path));
+    }
+
+    /// Takes in whatever the loading thread has produced since the last frame.
+    pub fn poll_loader(&mut self, ctx: &egui::Context) {
+        let Some(loader) = &mut self.loader else {
+            return;
+        };
+        // Keep polling until the thread is done.
+        ctx.request_repaint();
+        while let Ok(event) = loader.receiver.try_recv() {
+            match event {
+                LoadEvent::Listed(project) => {
+                    loader.total = Some(project.images.len());
+                    self.project = Some(*project);
+                    self.stats = None;
+                    self.timeline_follow = None;
+                    self.gallery_follow = None;
+                }
+                LoadEvent::Labels(index, Ok(mut labels)) => {
+                    loader.warnings.extend(labels.warning.take());
+                    if let Some(project) = &mut self.project {
+                        project.images[index].set_labels(labels);
+                        project.other_label_counts = None;
+                        project.crop_boxes = None;
+                    }
+                }
+                LoadEvent::Labels(_, Err(err)) => loader.errors.push(format!("{:#}", err)),
+                LoadEvent::Failed(err) => {
+                    if !loader.progress.cancel.load(Ordering::Relaxed) {
+                        self.message_box = Some(format!("{}", err));
+                    }
+                    self.loader = None;
+                    return;
+                }
+                LoadEvent::Done => {
+                    if let Some(project) = &mut self.project {
+                        // Box counts are known now. A bad pattern was already
+                        // reported when it was typed.
+                        let _ = project.refresh_order();
+                    }
+                    let unread = loader.total.unwrap_or(0)
+                        - loader.progress.parsed.load(Ordering::Relaxed)
+                        + loader.errors.len();
+                    let mut message = vec![];
+                    if unread > 0 {
+                        message.push(format!(
+                            "Labels of {} images were not read and won't be saved.",
+                            unread
+                        ));
+                        message.extend(loader.errors.iter().take(5).cloned());
+                    }
+                    if !loader.warnings.is_empty() {
+                        message.push(format!(
+                            "Box attributes of {} images were unreadable or didn't match their boxes, and were dropped.",
+                            loader.warnings.len()
+                        ));
+                        message.extend(loader.warnings.iter().take(5).cloned());
+                    }
+                    if !message.is_empty() {
+                        self.message_box = Some(message.join("\n"));
+                    }
+                    self.loader = None;
+                    return;
+                }
+            }
+        }
+    }
+
+    pub fn load_progress(&mut self, ui: &mut egui::Ui) {
+        let Some(loader) = &self.loader else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            match loader.total {
+                None => {
+                    ui.spinner();
+                    ui.label(format!(
+                        "Scanning, {} files",
+                        loader.progress.scanned.load(Ordering::Relaxed)
+                    ));
+                }
+                Some(total) => {
+                    let parsed = loader.progress.parsed.load(Ordering::Relaxed);
+                    ui.add(
+                        egui::ProgressBar::new(parsed as f32 / total.max(1) as f32)
+                            .desired_width(200.0)
+                            .text(format!("{} / {} labels", parsed, total)),
+                    );
+                }
+            }
+            if ui.button("Cancel").clicked() {
+                loader.cancel();
+            }
+        });
+    }
+}
@@ -0,0 +1,206 @@
+use image::ImageDecoder;
+use image::metadata::Orientation;
+use std::path::Path;
+
+/// Which way up the training pipeline sees images with an EXIF orientation.
+/// Labelel always shows them upright, this only decides the frame label
+/// files are read and written in.
+#[derive(PartialEq, Eq, Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub enum OrientationPolicy {
+    /// The trainer applies the EXIF orientation, as Ultralytics does.
+    #[default]
+    Upright,
+    /// The trainer reads the stored pixels as they are.
+    Raw,
+}
+
+impl OrientationPolicy {
+    pub fn name(self) -> &'static str {
+        match self {
+            OrientationPolicy::Upright => "Trainer applies EXIF",
+            OrientationPolicy::Raw => "Trainer ignores EXIF",
+        }
+    }
+
+    /// Maps a normalized box from the upright image to the frame of the label
+    /// files.
+    pub fn stored_box(
+        self,
+        orientation: Orientation,
+        center: egui::Pos2,
+        size: egui::Vec2,
+    ) -> (egui::Pos2, egui::Vec2) {
+        match self {
+            OrientationPolicy::Upright => (center, size),
+            OrientationPolicy::Raw => from_upright(orientation, center, size),
+        }
+    }
+
+    /// Inverse of [`OrientationPolicy::stored_box`].
+    pub fn upright_box(
+        self,
+        orientation: Orientation,
+        center: egui::Pos2,
+        size: egui::Vec2,
+    ) -> (egui::Pos2, egui::Vec2) {
+        match self {
+            OrientationPolicy::Upright => (center, size),
+            OrientationPolicy::Raw => to_upright(orientation, center, size),
+        }
+    }
+
+    /// Pixel size in the frame of the label files, from the upright size.
+    pub fn stored_size(self, orientation: Orientation, size: egui::Vec2) -> egui::Vec2 {
+        match self {
+            OrientationPolicy::Raw if swaps_axes(orientation) => egui::vec2(size.y, size.x),
+            _ => size,
+        }
+    }
+}
+
+/// Reads the EXIF orientation from the image header, without decoding pixels.
+pub fn probe(path: &Path) -> Orientation {
+    image::ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .ok()
+        .and_then(|reader| reader.into_decoder().ok())
+        .and_then(|mut decoder| decoder.orientation().ok())
+        .unwrap_or(Orientation::NoTransforms)
+}
+
+pub fn swaps_axes(orientation: Orientation) -> bool {
+    matches!(
+        orientation,
+        Orientation::Rotate90
+            | Orientation::Rotate270
+            | Orientation::Rotate90FlipH
+            | Orientation::Rotate270FlipH
+    )
+}
+
+/// Maps a normalized box from the stored pixels to the upright image.
+pub fn to_upright(
+    orientation: Orientation,
+    center: egui::Pos2,
+    size: egui::Vec2,
+) -> (egui::Pos2, egui::Vec2) {
+    let (x, y) = (center.x, center.y);
+    let center = match orientation {
+        Orientation::NoTransforms => (x, y),
+        Orientation::Rotate90 => (1.0 - y, x),
+        Orientation::Rotate180 => (1.0 - x, 1.0 - y),
+        Orientation::Rotate270 => (y, 1.0 - x),
+        Orientation::FlipHorizontal => (1.0 - x, y),
+        Orientation::FlipVertical => (x, 1.0 - y),
+        Orientation::Rotate90FlipH => (y, x),
+        Orientation::Rotate270FlipH => (1.0 - y, 1.0 - x),
+    };
+    let size = if swaps_axes(orientation) {
+        egui::vec2(size.y, size.x)
+    } else {
+        size
+    };
+    (center.into(), size)
+}
+
+/// Inverse of [`to_upright`].
+pub fn from_upright(
+    orientation: Orientation,
+    center: egui::Pos2,
+    size: egui::Vec2,
+) -> (egui::Pos2, egui::Vec2) {
+    let inverse = match orientation {
+        Orientation::Rotate90 => Orientation::Rotate270,
+        Orientation::Rotate270 => Orientation::Rotate90,
+        // Everything else undoes itself.
+        other => other,
+    };
+    to_upright(inverse, center, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Orientation; 8] = [
+        Orientation::NoTransforms,
+        Orientation::Rotate90,
+        Orientation::Rotate180,
+        Orientation::Rotate270,
+        Orientation::FlipHorizontal,
+        Orientation::FlipVertical,
+        Orientation::Rotate90FlipH,
+        Orientation::Rotate270FlipH,
+    ];
+
+    fn assert_close(a: (egui::Pos2, egui::Vec2), b: (egui::Pos2, egui::Vec2)) {
+        assert!(
+            (a.0 - b.0).length() < 1e-6 && (a.1 - b.1).length() < 1e-6,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn from_upright_undoes_to_upright() {
+        let stored = (egui::pos2(0.2, 0.7), egui::vec2(0.1, 0.3));
+        for orientation in ALL {
+            let upright = to_upright(orientation, stored.0, stored.1);
+            assert_close(from_upright(orientation, upright.0, upright.1), stored);
+            let back = from_upright(orientation, stored.0, stored.1);
+            assert_close(to_upright(orientation, back.0, back.1), stored);
+        }
+    }
+
+    /// A single lit pixel has to end up where `image` puts it when it applies
+    /// the orientation.
+    #[test]
+    fn to_upright_matches_the_decoder() {
+        let (width, height) = (4, 2);
+        for orientation in ALL {
+            let mut pixels = image::GrayImage::new(width, height);
+            pixels.put_pixel(1, 0, image::Luma([255]));
+            let mut image = image::DynamicImage::ImageLuma8(pixels);
+            image.apply_orientation(orientation);
+            let upright = image.to_luma8();
+            let (x, y, _) = upright
+                .enumerate_pixels()
+                .find(|(_, _, pixel)| pixel.0[0] == 255)
+                .unwrap();
+            let (w, h) = (upright.width() as f32, upright.height() as f32);
+
+            let size = egui::vec2(1.0 / width as f32, 1.0 / height as f32);
+            let center = egui::pos2(1.5 / width as f32, 0.5 / height as f32);
+            assert_close(
+                to_upright(orientation, center, size),
+                (
+                    egui::pos2((x as f32 + 0.5) / w, (y as f32 + 0.5) / h),
+                    egui::vec2(1.0 / w, 1.0 / h),
+                ),
+            );
+        }
+    }
+
+    #[test]
+    fn policy_only_moves_raw_boxes() {
+        let center = egui::pos2(0.2, 0.7);
+        let size = egui::vec2(0.1, 0.3);
+        let pixels = egui::vec2(640.0, 480.0);
+        for orientation in ALL {
+            let upright = OrientationPolicy::Upright;
+            assert_close(
+                upright.stored_box(orientation, center, size),
+                (center, size),
+            );
+            assert_eq!(upright.stored_size(orientation, pixels), pixels);
+
+            let raw = OrientationPolicy::Raw;
+            let stored = raw.stored_box(orientation, center, size);
+            assert_close(
+                raw.upright_box(orientation, stored.0, stored.1),
+                (center, size),
+            );
+            let stored_size = raw.stored_size(orientation, pixels);
+            assert_eq!(stored_size == pixels, !swaps_axes(orientation));
+        }
+    }
+}
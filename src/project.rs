@@ -1,27 +1,70 @@
 use crate::app::SegmentDrag;
-use crate::colors::COLORS;
+use crate::attributes;
+use crate::attributes::AttributeSpec;
+use crate::attributes::Attributes;
+use crate::colors::Palette;
+use crate::crops::ClassBoxes;
+use crate::filter::ImageFilter;
+use crate::loader::LoadProgress;
+use crate::orientation;
+use crate::orientation::OrientationPolicy;
+use crate::sidecar::Sidecar;
+use crate::stats::count_labels;
+use crate::tags;
+use crate::tags::TagGroup;
+use crate::timeline::PickedAction;
+use crate::validation;
 use crate::yolo::YoloDataConfig;
 
 use egui::ahash::HashMap;
+use image::metadata::Orientation;
 use serde_yaml::Number;
 use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::fs;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Write;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::time::SystemTime;
 
-#[derive(Default)]
 pub struct Label {
     pub name: String,
     pub color: egui::Color32,
+    /// Whether `color` was picked by hand rather than taken from the palette.
+    pub custom_color: bool,
+    pub visible: bool,
 }
 
+#[derive(Clone)]
 pub struct Segment {
     pub center: egui::Pos2,
     pub size: egui::Vec2,
     pub label_id: u32,
+    pub selected: bool,
+    pub hidden: bool,
+    /// Extra columns of prediction and tracker output, kept as they are.
+    pub confidence: Option<f32>,
+    pub track_id: Option<u32>,
+    /// Values of the attributes in the schema of the box's class.
+    pub attributes: Attributes,
+}
+
+impl Segment {
+    /// Moves the box to another class, dropping attributes not in its schema.
+    pub fn set_label(&mut self, label_id: u32, schema: &BTreeMap<u32, Vec<AttributeSpec>>) {
+        if label_id == self.label_id {
+            return;
+        }
+        self.label_id = label_id;
+        let specs = schema.get(&label_id).map_or(&[][..], Vec::as_slice);
+        self.attributes
+            .retain(|name, _| specs.iter().any(|spec| spec.name == *name));
+    }
 }
 
 pub struct Image {
@@ -30,6 +73,87 @@ pub struct Image {
     pub labels_file_path: PathBuf,
     pub segments: Vec<Segment>,
     pub uri: String,
+    /// Upright size in pixels, read from the header along with the labels.
+    pub pixel_size: Option<egui::Vec2>,
+    /// Modification time, read with the labels for sorting.
+    pub modified: Option<SystemTime>,
+    /// Whether the image needs our own decoder to be displayed, probed on first view.
+    pub high_bit_depth: Option<bool>,
+    /// EXIF orientation. Segments are always relative to the upright image.
+    pub orientation: Orientation,
+    /// An existing label file without boxes marks a checked background image.
+    pub has_labels_file: bool,
+    /// Whether the label file has been read. Images whose labels never got
+    /// read, e.g. after cancelling the load, are left alone when saving.
+    pub labels_loaded: bool,
+    /// Bookmarks such as "ask the client", kept in the sidecar.
+    pub flags: BTreeSet<String>,
+    pub note: String,
+    /// Tags of each tag group, by group name.
+    pub tags: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// What [`read_labels`] found out about an image.
+pub struct ImageLabels {
+    pub segments: Vec<Segment>,
+    pub orientation: Orientation,
+    /// Upright size, if the image header could be read.
+    pub pixel_size: Option<egui::Vec2>,
+    pub has_labels_file: bool,
+    pub modified: Option<SystemTime>,
+    /// Why the box attributes were dropped, if they were.
+    pub warning: Option<String>,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum ImageStatus {
+    Unlabelled,
+    Background,
+    Labelled,
+}
+
+impl ImageStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageStatus::Unlabelled => "Unlabelled",
+            ImageStatus::Background => "Background",
+            ImageStatus::Labelled => "Labelled",
+        }
+    }
+}
+
+impl Image {
+    pub fn set_labels(&mut self, labels: ImageLabels) {
+        // A size taken from the texture before the orientation was known may
+        // be the wrong way round.
+        self.pixel_size = labels.pixel_size;
+        self.orientation = labels.orientation;
+        self.modified = labels.modified;
+        self.has_labels_file |= labels.has_labels_file;
+        // Keep boxes drawn before the file was read.
+        self.segments.splice(0..0, labels.segments);
+        self.labels_loaded = true;
+    }
+
+    pub fn status(&self) -> ImageStatus {
+        if !self.segments.is_empty() {
+            ImageStatus::Labelled
+        } else if self.has_labels_file {
+            ImageStatus::Background
+        } else {
+            ImageStatus::Unlabelled
+        }
+    }
+}
+
+/// Kinds of images [`Project::seek`] looks for.
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum Seek {
+    Unlabelled,
+    /// Images with a box of the selected class.
+    Class,
+    /// Images with a problem found by [`validation::problems`].
+    Problem,
 }
 
 #[derive(PartialEq, Eq, Copy, Clone)]
@@ -42,19 +166,66 @@ pub enum Tool {
 pub struct Project {
     pub original_yaml: Value,
     pub labels: HashMap<u32, Label>,
+    pub palette: Palette,
+    pub orientation_policy: OrientationPolicy,
     pub images: Vec<Image>,
     pub image_index: usize,
+    pub filter: ImageFilter,
+    /// Number of images Page Up and Page Down skip.
+    pub jump_size: usize,
+    pub goto_query: String,
+    /// Flag the bookmark key toggles.
+    pub flag: String,
+    pub bookmark_search: String,
+    pub tag_groups: Vec<TagGroup>,
+    /// Attributes boxes of each class can have.
+    pub attribute_schema: BTreeMap<u32, Vec<AttributeSpec>>,
+    /// Indices of the images that pass `filter`, in its sort order.
+    pub order: Vec<usize>,
     pub label_id: Option<u32>,
+    /// When set, only boxes of this class are shown and editable.
+    pub solo_label: Option<u32>,
+    /// Stamp size as a fraction of the image size.
     pub rect_size: egui::Vec2,
+    /// Most recent box size of each class, restored when the class is picked.
+    pub stamp_sizes: HashMap<u32, egui::Vec2>,
+    pub lock_aspect: bool,
+    /// Class bound to each of the digit keys, see [`crate::app::DIGIT_KEYS`].
+    pub hotkeys: [Option<u32>; 10],
     pub tool: Tool,
     pub drag_start_pos: Option<egui::Pos2>,
     pub edit_drag: Option<SegmentDrag>,
+    pub select_drag_start: Option<egui::Pos2>,
+    /// Image the drags above started on. They are dropped when it changes.
+    pub drag_image_index: usize,
+    /// Segments under the cursor, smallest first, and which of them is active.
+    pub hover_candidates: Vec<usize>,
+    pub hover_cycle: usize,
+    /// Screen rect the current image was drawn at last frame.
+    pub view_rect: Option<egui::Rect>,
+    /// Images picked in the timeline for batch operations.
+    pub picked_images: BTreeSet<usize>,
+    /// Last image clicked in the timeline, where shift-click ranges start.
+    pub pick_anchor: Option<usize>,
     pub add_label_modal: Option<(u32, String)>,
+    /// Class pending deletion and the class its boxes move to, if any.
+    pub delete_label_modal: Option<(u32, Option<u32>)>,
+    pub merge_labels: (Option<u32>, Option<u32>),
+    pub picked_action_modal: Option<PickedAction>,
+    /// Box counts of every image but the one at the given index, see
+    /// [`Project::label_counts`]. Cleared by anything that edits the boxes of
+    /// other images.
+    pub other_label_counts: Option<(usize, HashMap<u32, usize>)>,
+    /// Boxes the crop gallery lists. Cleared by anything that edits boxes
+    /// while it's open.
+    pub crop_boxes: Option<ClassBoxes>,
     pub yaml_file_path: PathBuf,
 }
 
 impl Project {
-    pub fn load(yaml_file_path: PathBuf) -> anyhow::Result<Project> {
+    /// Reads the dataset config and lists its images. Their labels are read
+    /// separately with [`read_labels`], see [`crate::loader`].
+    pub fn open(yaml_file_path: PathBuf, progress: &LoadProgress) -> anyhow::Result<Project> {
         let contents = fs::read_to_string(&yaml_file_path)?;
         // let yaml: YoloDataConfig = serde_yaml::from_str(&contents)?;
         let yaml: Value = serde_yaml::from_str(&contents)?;
@@ -65,38 +236,25 @@ impl Project {
         dbg!(&base);
         let train_dir_path = base.join(&yolo.train);
         dbg!(&train_dir_path);
+        let mut sidecar = Sidecar::read(&yaml_file_path)?;
         let mut images = vec![];
         for file in fs::read_dir(train_dir_path)? {
+            if progress.cancel.load(Ordering::Relaxed) {
+                anyhow::bail!("Loading cancelled");
+            }
             let file = file?;
+            progress.scanned.fetch_add(1, Ordering::Relaxed);
             if file.file_type()?.is_file()
                 && (file.path().to_string_lossy().ends_with(".png")
-                    || file.path().to_string_lossy().ends_with(".jpg"))
+                    || file.path().to_string_lossy().ends_with(".jpg")
+                    || file.path().to_string_lossy().ends_with(".tif")
+                    || file.path().to_string_lossy().ends_with(".tiff"))
             {
                 let mut labels_file_path =
                     PathBuf::from(file.path().to_string_lossy().replace("images", "labels"));
 
                 labels_file_path.set_extension("txt");
 
-                let mut segments = vec![];
-                if let Ok(file) = File::open(&labels_file_path) {
-                    let reader = BufReader::new(file);
-                    for line in reader.lines() {
-                        let line = line?;
-                        let parts: Vec<_> = line.split(' ').collect();
-                        segments.push(Segment {
-                            center: egui::Pos2 {
-                                x: parts[1].parse()?,
-                                y: parts[2].parse()?,
-                            },
-                            size: egui::Vec2 {
-                                x: parts[3].parse()?,
-                                y: parts[4].parse()?,
-                            },
-                            label_id: parts[0].parse()?,
-                        });
-                    }
-                };
-
                 let file_name = file
                     .path()
                     .file_name()
@@ -105,50 +263,108 @@ impl Project {
                     .to_string();
                 let mut uri = "file://".to_string();
                 uri.push_str(&file.path().to_string_lossy());
+                let notes = sidecar.images.remove(&file_name).unwrap_or_default();
 
                 images.push(Image {
+                    flags: notes.flags,
+                    note: notes.note,
+                    tags: notes.tags,
                     uri,
                     file_name,
                     labels_file_path,
                     file_path: file.path(),
-                    segments,
+                    segments: vec![],
+                    pixel_size: None,
+                    modified: None,
+                    high_bit_depth: None,
+                    orientation: Orientation::NoTransforms,
+                    has_labels_file: false,
+                    labels_loaded: false,
                 });
             }
         }
 
         let mut labels = HashMap::default();
         for (&index, name) in &yolo.names {
+            let color = sidecar
+                .colors
+                .get(&index)
+                .and_then(|hex| egui::Color32::from_hex(hex).ok())
+                .unwrap_or(sidecar.palette.color(index));
             labels.insert(
                 index,
                 Label {
                     name: name.clone(),
-                    color: COLORS[index as usize % COLORS.len()],
+                    color,
+                    custom_color: sidecar.colors.contains_key(&index),
+                    visible: true,
                 },
             );
         }
 
+        let hotkeys = match sidecar.hotkeys {
+            Some(hotkeys) => hotkeys.map(|key| key.filter(|id| labels.contains_key(id))),
+            None => {
+                let mut hotkeys = [None; 10];
+                let mut label_ids: Vec<u32> = labels.keys().copied().collect();
+                label_ids.sort();
+                for (key, id) in hotkeys.iter_mut().zip(label_ids) {
+                    *key = Some(id);
+                }
+                hotkeys
+            }
+        };
+
         images.sort_by(|a, b| a.file_path.file_name().cmp(&b.file_path.file_name()));
 
         Ok(Project {
+            order: (0..images.len()).collect(),
+            filter: ImageFilter::default(),
+            jump_size: 10,
+            goto_query: String::new(),
+            flag: "bookmark".to_string(),
+            bookmark_search: String::new(),
+            tag_groups: sidecar.tag_groups,
+            attribute_schema: sidecar.attribute_schema,
             yaml_file_path,
             original_yaml: yaml,
             images,
             labels,
+            palette: sidecar.palette,
+            orientation_policy: sidecar.orientation_policy,
             image_index: 0,
-            rect_size: egui::Vec2::splat(64.0),
+            rect_size: egui::Vec2::splat(0.1),
+            stamp_sizes: HashMap::default(),
+            lock_aspect: false,
+            hotkeys,
             label_id: None,
+            solo_label: None,
             tool: Tool::Stamp,
             drag_start_pos: None,
             edit_drag: None,
+            select_drag_start: None,
+            drag_image_index: 0,
+            hover_candidates: vec![],
+            hover_cycle: 0,
+            view_rect: None,
+            picked_images: BTreeSet::new(),
+            pick_anchor: None,
             add_label_modal: None,
+            delete_label_modal: None,
+            merge_labels: (None, None),
+            picked_action_modal: None,
+            other_label_counts: None,
+            crop_boxes: None,
         })
     }
 
     pub fn save(&mut self) -> anyhow::Result<()> {
         let nc = self.labels.len();
+        let mut labels: Vec<_> = self.labels.iter().collect();
+        labels.sort_by_key(|(id, _)| **id);
         let v = Value::Mapping(
-            self.labels
-                .iter()
+            labels
+                .into_iter()
                 .map(|(id, label)| (Value::Number(Number::from(*id)), label.name.clone().into()))
                 .collect(),
         );
@@ -160,39 +376,756 @@ impl Project {
             "{}",
             &serde_yaml::to_string(&self.original_yaml)?
         )?;
+        Sidecar::write(self)?;
 
-        for image in &self.images {
-            if image.segments.is_empty() {
+        for image in &mut self.images {
+            if !image.labels_loaded {
+                continue;
+            }
+            // Still rewrite emptied files, e.g. after deleting a class.
+            if image.segments.is_empty() && !image.labels_file_path.exists() {
                 continue;
             }
             let path = &image.labels_file_path;
             let mut file = File::create(path)?;
             for segment in &image.segments {
-                writeln!(
+                let (center, size) = self.orientation_policy.stored_box(
+                    image.orientation,
+                    segment.center,
+                    segment.size,
+                );
+                write!(
                     &mut file,
                     "{} {} {} {} {}",
                     segment.label_id,
-                    segment.center.x.clamp(0.0, 1.0),
-                    segment.center.y.clamp(0.0, 1.0),
-                    segment.size.x.clamp(0.0, 1.0),
-                    segment.size.y.clamp(0.0, 1.0)
+                    center.x.clamp(0.0, 1.0),
+                    center.y.clamp(0.0, 1.0),
+                    size.x.clamp(0.0, 1.0),
+                    size.y.clamp(0.0, 1.0)
                 )?;
+                if segment.confidence.is_some() || segment.track_id.is_some() {
+                    write!(&mut file, " {}", segment.confidence.unwrap_or(1.0))?;
+                }
+                if let Some(track_id) = segment.track_id {
+                    write!(&mut file, " {}", track_id)?;
+                }
+                writeln!(&mut file)?;
             }
+            attributes::write(path, &image.segments)?;
+            image.has_labels_file = true;
             println!("Saved {:?}", path);
         }
 
         Ok(())
     }
 
+    /// Position of the current image in [`Project::order`], if it passes the
+    /// filter.
+    pub fn position(&self) -> Option<usize> {
+        self.order.iter().position(|&i| i == self.image_index)
+    }
+
+    pub fn go_to_position(&mut self, position: usize) {
+        if let Some(&index) = self
+            .order
+            .get(position.min(self.order.len().saturating_sub(1)))
+        {
+            self.image_index = index;
+        }
+    }
+
     pub fn advance(&mut self) {
-        self.image_index = self
+        self.go_to_position(self.position().map_or(0, |p| p + 1));
+    }
+
+    pub fn jump(&mut self, delta: isize) {
+        let position = self.position().unwrap_or(0);
+        self.go_to_position(position.saturating_add_signed(delta));
+    }
+
+    /// Moves to the next image of a kind, wrapping around the listed images.
+    /// Returns false if there is none.
+    pub fn seek(&mut self, seek: Seek, forward: bool) -> bool {
+        let found = |image: &Image| match seek {
+            Seek::Unlabelled => image.status() == ImageStatus::Unlabelled,
+            Seek::Class => self
+                .label_id
+                .is_some_and(|id| image.segments.iter().any(|s| s.label_id == id)),
+            Seek::Problem => !validation::problems(image, &self.labels).is_empty(),
+        };
+        let count = self.order.len();
+        let start = self.position();
+        for step in 1..=count {
+            let position = match (start, forward) {
+                (Some(p), true) => (p + step) % count,
+                (Some(p), false) => (p + count * 2 - step) % count,
+                (None, true) => step - 1,
+                (None, false) => count - step,
+            };
+            let index = self.order[position];
+            if found(&self.images[index]) {
+                self.image_index = index;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Moves to the image named `query`, or else the first whose name
+    /// contains it. Images hidden by the filter are found too, and clear it.
+    /// An empty query finds nothing.
+    pub fn go_to_name(&mut self, query: &str) -> bool {
+        if query.is_empty() {
+            return false;
+        }
+        let query = query.to_lowercase();
+        let names: Vec<String> = self
             .images
-            .len()
-            .saturating_sub(1)
-            .min(self.image_index + 1);
+            .iter()
+            .map(|image| image.file_name.to_lowercase())
+            .collect();
+        let found = names
+            .iter()
+            .position(|name| *name == query)
+            .or_else(|| names.iter().position(|name| name.contains(&query)));
+        if let Some(index) = found {
+            self.go_to(index);
+        }
+        found.is_some()
+    }
+
+    /// Moves to an image, clearing the filter if it hides the image.
+    pub fn go_to(&mut self, index: usize) {
+        self.image_index = index;
+        if !self.order.contains(&index) {
+            self.filter = ImageFilter {
+                sort: self.filter.sort,
+                seed: self.filter.seed,
+                ..ImageFilter::default()
+            };
+            // Without a name pattern there's nothing to fail.
+            let _ = self.refresh_order();
+        }
+    }
+
+    /// Every flag in use, and the one the bookmark key sets.
+    pub fn flags(&self) -> BTreeSet<String> {
+        let mut flags: BTreeSet<String> = self
+            .images
+            .iter()
+            .flat_map(|image| image.flags.iter().cloned())
+            .collect();
+        flags.insert(self.flag.clone());
+        flags
+    }
+
+    pub fn toggle_flag(&mut self, flag: &str) {
+        if let Some(image) = self.images.get_mut(self.image_index)
+            && !image.flags.remove(flag)
+        {
+            image.flags.insert(flag.to_string());
+        }
+    }
+
+    /// Sets or clears a tag of the current image. Setting a tag of a single
+    /// choice group clears the others.
+    pub fn toggle_tag(&mut self, group: usize, tag: usize) {
+        let Some(group) = self.tag_groups.get(group) else {
+            return;
+        };
+        let Some(tag) = group.tags.get(tag) else {
+            return;
+        };
+        let Some(image) = self.images.get_mut(self.image_index) else {
+            return;
+        };
+        let tags = image.tags.entry(group.name.clone()).or_default();
+        if !tags.remove(tag) {
+            if !group.multiple {
+                tags.clear();
+            }
+            tags.insert(tag.clone());
+        }
+        if tags.is_empty() {
+            image.tags.remove(&group.name);
+        }
+    }
+
+    pub fn add_tag_group(&mut self, name: &str, multiple: bool) {
+        if self.tag_groups.iter().all(|group| group.name != name) {
+            self.tag_groups.push(TagGroup {
+                name: name.to_string(),
+                multiple,
+                tags: vec![],
+            });
+        }
+    }
+
+    /// Adds a tag to a group. Tags name the class folders of the export, so
+    /// names that aren't a single path component are refused.
+    pub fn add_tag(&mut self, group: usize, tag: &str) -> anyhow::Result<()> {
+        tags::check_tag_name(tag)?;
+        let Some(group) = self.tag_groups.get_mut(group) else {
+            anyhow::bail!("No tag group to add \"{}\" to", tag);
+        };
+        if !group.tags.iter().any(|t| t == tag) {
+            group.tags.push(tag.to_string());
+        }
+        Ok(())
+    }
+
+    /// Removes a tag group along with its tags on every image.
+    pub fn remove_tag_group(&mut self, group: usize) {
+        let group = self.tag_groups.remove(group);
+        for image in &mut self.images {
+            image.tags.remove(&group.name);
+        }
+    }
+
+    /// Removes a tag from its group and from every image.
+    pub fn remove_tag(&mut self, group: usize, tag: usize) {
+        let group = &mut self.tag_groups[group];
+        let tag = group.tags.remove(tag);
+        for image in &mut self.images {
+            if let Some(tags) = image.tags.get_mut(&group.name) {
+                tags.remove(&tag);
+                if tags.is_empty() {
+                    image.tags.remove(&group.name);
+                }
+            }
+        }
+    }
+
+    /// Reapplies [`Project::filter`] to the images as they are now.
+    pub fn refresh_order(&mut self) -> Result<(), regex::Error> {
+        self.order = self.filter.apply(&self.images)?;
+        // Stay on a listed image, so stepping through them carries on from here.
+        if !self.order.contains(&self.image_index)
+            && let Some(&nearest) = self
+                .order
+                .iter()
+                .min_by_key(|&&i| i.abs_diff(self.image_index))
+        {
+            self.image_index = nearest;
+        }
+        Ok(())
+    }
+
+    pub fn select_label(&mut self, label_id: u32) {
+        self.label_id = Some(label_id);
+        if let Some(&size) = self.stamp_sizes.get(&label_id) {
+            self.rect_size = size;
+        }
+    }
+
+    pub fn label_visible(&self, label_id: u32) -> bool {
+        match self.solo_label {
+            Some(solo) => solo == label_id,
+            None => self.labels.get(&label_id).is_none_or(|l| l.visible),
+        }
+    }
+
+    /// Recolors every class from `palette`, keeping custom colors.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        for (&id, label) in &mut self.labels {
+            if !label.custom_color {
+                label.color = palette.color(id);
+            }
+        }
+    }
+
+    pub fn next_label_id(&self) -> u32 {
+        self.labels.keys().max().map_or(0, |id| id + 1)
+    }
+
+    /// Removes a class, moving its boxes to `reassign` or dropping them.
+    pub fn delete_label(&mut self, label_id: u32, reassign: Option<u32>) -> anyhow::Result<()> {
+        self.ensure_labels_loaded()?;
+        self.labels.remove(&label_id);
+        self.attribute_schema.remove(&label_id);
+        if let Some(into) = reassign {
+            for image in &mut self.images {
+                for segment in &mut image.segments {
+                    if segment.label_id == label_id {
+                        segment.set_label(into, &self.attribute_schema);
+                    }
+                }
+            }
+        }
+        self.remap_label_ids(|id| if id == label_id { reassign } else { Some(id) });
+        Ok(())
+    }
+
+    /// Exchanges the ids of two classes. `b` doesn't have to exist, in which
+    /// case `a` is simply renumbered.
+    pub fn swap_label_ids(&mut self, a: u32, b: u32) -> anyhow::Result<()> {
+        self.ensure_labels_loaded()?;
+        let label_a = self.labels.remove(&a);
+        let label_b = self.labels.remove(&b);
+        if let Some(label) = label_a {
+            self.labels.insert(b, label);
+        }
+        if let Some(label) = label_b {
+            self.labels.insert(a, label);
+        }
+        self.remap_label_ids(|id| {
+            Some(match id {
+                id if id == a => b,
+                id if id == b => a,
+                id => id,
+            })
+        });
+        Ok(())
+    }
+
+    /// Renumbers classes to `0..nc` keeping their order, as YOLO expects.
+    /// Boxes of unknown classes keep their ids.
+    pub fn compact_label_ids(&mut self) -> anyhow::Result<()> {
+        self.ensure_labels_loaded()?;
+        let mut ids: Vec<u32> = self.labels.keys().copied().collect();
+        ids.sort();
+        let new_ids: HashMap<u32, u32> = ids.into_iter().zip(0..).collect();
+        self.labels = std::mem::take(&mut self.labels)
+            .into_iter()
+            .map(|(id, label)| (new_ids[&id], label))
+            .collect();
+        self.remap_label_ids(|id| Some(new_ids.get(&id).copied().unwrap_or(id)));
+        Ok(())
+    }
+
+    /// Class ids can only be rewritten once every label file has been read,
+    /// or the ones still loading would keep the old ids.
+    fn ensure_labels_loaded(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.images.iter().all(|image| image.labels_loaded),
+            "Classes can't be changed until all labels are loaded"
+        );
+        Ok(())
+    }
+
+    /// Rewrites every reference to a class id, dropping boxes mapped to `None`.
+    fn remap_label_ids(&mut self, map: impl Fn(u32) -> Option<u32>) {
+        for image in &mut self.images {
+            image
+                .segments
+                .retain_mut(|segment| match map(segment.label_id) {
+                    Some(id) => {
+                        segment.label_id = id;
+                        true
+                    }
+                    None => false,
+                });
+        }
+        for hotkey in &mut self.hotkeys {
+            *hotkey = hotkey.and_then(&map);
+        }
+        self.stamp_sizes = std::mem::take(&mut self.stamp_sizes)
+            .into_iter()
+            .filter_map(|(id, size)| map(id).map(|id| (id, size)))
+            .collect();
+        self.attribute_schema = std::mem::take(&mut self.attribute_schema)
+            .into_iter()
+            .filter_map(|(id, schema)| map(id).map(|id| (id, schema)))
+            .collect();
+        self.label_id = self.label_id.and_then(&map);
+        let (from, into) = self.merge_labels;
+        self.merge_labels = (from.and_then(&map), into.and_then(&map));
+        self.solo_label = self.solo_label.and_then(&map);
+        self.edit_drag = None;
+        self.hover_candidates.clear();
+        self.other_label_counts = None;
+        self.crop_boxes = None;
+        // Palette colors follow the id, as they will after reopening.
+        for (&id, label) in &mut self.labels {
+            if !label.custom_color {
+                label.color = self.palette.color(id);
+            }
+        }
+    }
+
+    /// Box count of every class over the dataset. Only the current image is
+    /// counted each time, the rest is cached.
+    pub fn label_counts(&mut self) -> HashMap<u32, usize> {
+        let index = self.image_index;
+        let others = match self.other_label_counts.take() {
+            Some((cached, counts)) if cached == index => counts,
+            // Moving to another image only swaps which one is left out.
+            Some((cached, mut counts)) if cached < self.images.len() => {
+                for segment in &self.images[cached].segments {
+                    *counts.entry(segment.label_id).or_default() += 1;
+                }
+                for segment in self.images.get(index).into_iter().flat_map(|i| &i.segments) {
+                    let count = counts.entry(segment.label_id).or_default();
+                    *count = count.saturating_sub(1);
+                }
+                counts
+            }
+            _ => count_labels(
+                self.images
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| i != index)
+                    .map(|(_, image)| image),
+            ),
+        };
+        let mut counts = others.clone();
+        self.other_label_counts = Some((index, others));
+        for segment in self.images.get(index).into_iter().flat_map(|i| &i.segments) {
+            *counts.entry(segment.label_id).or_default() += 1;
+        }
+        counts
+    }
+
+    pub fn bind_hotkey(&mut self, key: usize, label_id: u32) {
+        for hotkey in &mut self.hotkeys {
+            if *hotkey == Some(label_id) {
+                *hotkey = None;
+            }
+        }
+        self.hotkeys[key] = Some(label_id);
+    }
+
+    /// Picks the class for drawing new boxes. With `relabel`, moves the
+    /// selected boxes, or else the hovered one, to the class instead.
+    pub fn assign_label(&mut self, label_id: u32, relabel: bool, hovered: Option<usize>) {
+        if !relabel {
+            self.select_label(label_id);
+            return;
+        }
+        let Some(image) = self.images.get(self.image_index) else {
+            return;
+        };
+        // Hidden boxes are left alone, even when selected.
+        let shown: Vec<usize> = (0..image.segments.len())
+            .filter(|&i| {
+                let segment = &image.segments[i];
+                !segment.hidden && self.label_visible(segment.label_id)
+            })
+            .collect();
+        let mut targets: Vec<usize> = shown
+            .iter()
+            .copied()
+            .filter(|&i| image.segments[i].selected)
+            .collect();
+        if targets.is_empty() {
+            targets.extend(hovered.filter(|i| shown.contains(i)));
+        }
+        let segments = &mut self.images[self.image_index].segments;
+        for i in targets {
+            segments[i].set_label(label_id, &self.attribute_schema);
+        }
+        self.crop_boxes = None;
     }
 
     pub fn back(&mut self) {
-        self.image_index = self.image_index.saturating_sub(1);
+        self.go_to_position(self.position().unwrap_or(0).saturating_sub(1));
+    }
+
+    /// Applies a shift or ctrl click on an image in the timeline or gallery to
+    /// the picked images. Returns false for a plain click, which is left to
+    /// the caller.
+    pub fn pick_image(&mut self, index: usize, modifiers: egui::Modifiers) -> bool {
+        if modifiers.shift {
+            let anchor = self.pick_anchor.unwrap_or(self.image_index);
+            // The range runs through the images as they are listed.
+            let position = |index| self.order.iter().position(|&i| i == index);
+            if let (Some(a), Some(b)) = (position(anchor), position(index)) {
+                let range = self.order[a.min(b)..=a.max(b)].to_vec();
+                self.picked_images.extend(range);
+            }
+        } else if modifiers.command {
+            if !self.picked_images.remove(&index) {
+                self.picked_images.insert(index);
+            }
+            self.pick_anchor = Some(index);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Replaces the boxes of every picked image with copies of the current
+    /// image's boxes.
+    pub fn copy_segments_to_picked(&mut self) {
+        let mut segments = self.images[self.image_index].segments.clone();
+        for segment in &mut segments {
+            segment.selected = false;
+            segment.hidden = false;
+        }
+        for &index in &self.picked_images {
+            if index != self.image_index {
+                self.images[index].segments = segments.clone();
+            }
+        }
+        self.other_label_counts = None;
+        self.crop_boxes = None;
+    }
+
+    pub fn clear_picked_segments(&mut self) {
+        for &index in &self.picked_images {
+            self.images[index].segments.clear();
+        }
+        self.hover_candidates.clear();
+        self.edit_drag = None;
+        self.other_label_counts = None;
+        self.crop_boxes = None;
+    }
+}
+
+/// Probes the orientation of an image and parses its label file, if any.
+pub fn read_labels(
+    file_path: &Path,
+    labels_file_path: &Path,
+    policy: OrientationPolicy,
+) -> anyhow::Result<ImageLabels> {
+    let orientation = orientation::probe(file_path);
+    let pixel_size = image::image_dimensions(file_path).ok().map(|(w, h)| {
+        let size = egui::vec2(w as f32, h as f32);
+        if orientation::swaps_axes(orientation) {
+            egui::vec2(size.y, size.x)
+        } else {
+            size
+        }
+    });
+    let modified = fs::metadata(file_path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let mut segments = vec![];
+    let mut warning = None;
+    let labels_file = File::open(labels_file_path);
+    let has_labels_file = labels_file.is_ok();
+    if let Ok(file) = labels_file {
+        let reader = BufReader::new(file);
+        for line in reader.lines() {
+            let line = line?;
+            let parts: Vec<_> = line.split_whitespace().collect();
+            if parts.is_empty() {
+                continue;
+            }
+            anyhow::ensure!(parts.len() >= 5, "Too few columns in {:?}", line);
+            // Boxes may be followed by a confidence and an integer track id.
+            // Anything longer is a polygon or keypoints, which saving as a box
+            // would destroy, so such files aren't loaded at all.
+            anyhow::ensure!(
+                parts.len() <= 6 || (parts.len() == 7 && parts[6].parse::<u32>().is_ok()),
+                "Polygons and keypoints aren't supported, found {:?}",
+                line
+            );
+            let center = egui::Pos2 {
+                x: parts[1].parse()?,
+                y: parts[2].parse()?,
+            };
+            let size = egui::Vec2 {
+                x: parts[3].parse()?,
+                y: parts[4].parse()?,
+            };
+            let (center, size) = policy.upright_box(orientation, center, size);
+            segments.push(Segment {
+                center,
+                size,
+                label_id: parts[0].parse()?,
+                selected: false,
+                hidden: false,
+                confidence: parts.get(5).and_then(|p| p.parse().ok()),
+                track_id: parts.get(6).and_then(|p| p.parse().ok()),
+                attributes: Attributes::default(),
+            });
+        }
+        // Attributes are matched to boxes by line, which only holds while the
+        // label file hasn't been edited elsewhere.
+        match attributes::read(labels_file_path) {
+            Ok(attributes) if attributes.is_empty() => {}
+            Ok(attributes) if attributes.len() == segments.len() => {
+                for (segment, attributes) in segments.iter_mut().zip(attributes) {
+                    segment.attributes = attributes;
+                }
+            }
+            Ok(attributes) => {
+                warning = Some(format!(
+                    "{}: {} box attributes for {} boxes",
+                    attributes::path(labels_file_path).display(),
+                    attributes.len(),
+                    segments.len()
+                ));
+            }
+            Err(err) => {
+                warning = Some(format!(
+                    "{}: {}",
+                    attributes::path(labels_file_path).display(),
+                    err
+                ));
+            }
+        }
+    };
+    Ok(ImageLabels {
+        segments,
+        orientation,
+        pixel_size,
+        modified,
+        has_labels_file,
+        warning,
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn segment(label_id: u32, center: egui::Pos2, size: egui::Vec2) -> Segment {
+        Segment {
+            center,
+            size,
+            label_id,
+            selected: false,
+            hidden: false,
+            confidence: None,
+            track_id: None,
+            attributes: Attributes::default(),
+        }
+    }
+
+    pub fn image(dir: &Path, segments: Vec<Segment>) -> Image {
+        Image {
+            file_path: dir.join("images/a.png"),
+            file_name: "a.png".to_string(),
+            labels_file_path: dir.join("labels/a.txt"),
+            segments,
+            uri: String::new(),
+            pixel_size: None,
+            modified: None,
+            high_bit_depth: None,
+            orientation: Orientation::NoTransforms,
+            has_labels_file: true,
+            labels_loaded: true,
+            flags: BTreeSet::new(),
+            note: String::new(),
+            tags: BTreeMap::new(),
+        }
+    }
+
+    /// A project with classes `ids` and one image holding a box of each of
+    /// `boxes`.
+    fn project(ids: &[u32], boxes: &[u32]) -> Project {
+        let dir = std::env::temp_dir().join(format!("labelel-test-{:?}-{:?}", ids, boxes));
+        fs::create_dir_all(dir.join("images")).unwrap();
+        let names: Vec<String> = ids.iter().map(|id| format!("{id}: class{id}")).collect();
+        fs::write(
+            dir.join("data.yaml"),
+            format!(
+                "train: images\nval: images\nnames: {{{}}}\n",
+                names.join(", ")
+            ),
+        )
+        .unwrap();
+        let mut project = Project::open(dir.join("data.yaml"), &LoadProgress::default()).unwrap();
+        let segments = boxes
+            .iter()
+            .map(|&id| segment(id, egui::pos2(0.5, 0.5), egui::vec2(0.1, 0.1)))
+            .collect();
+        project.images.push(image(&dir, segments));
+        project
+    }
+
+    fn box_ids(project: &Project) -> Vec<u32> {
+        project.images[0]
+            .segments
+            .iter()
+            .map(|s| s.label_id)
+            .collect()
+    }
+
+    fn name(project: &Project, id: u32) -> &str {
+        &project.labels[&id].name
+    }
+
+    #[test]
+    fn swap_exchanges_classes_and_boxes() {
+        let mut project = project(&[0, 1, 2], &[0, 1, 2]);
+        project.hotkeys[0] = Some(0);
+        project.swap_label_ids(0, 2).unwrap();
+        assert_eq!(box_ids(&project), [2, 1, 0]);
+        assert_eq!(name(&project, 0), "class2");
+        assert_eq!(name(&project, 2), "class0");
+        assert_eq!(project.hotkeys[0], Some(2));
+    }
+
+    #[test]
+    fn swap_with_free_id_renumbers() {
+        let mut project = project(&[0, 1], &[0, 1]);
+        project.swap_label_ids(1, 5).unwrap();
+        assert_eq!(box_ids(&project), [0, 5]);
+        assert!(!project.labels.contains_key(&1));
+        assert_eq!(name(&project, 5), "class1");
+    }
+
+    #[test]
+    fn delete_drops_or_moves_boxes() {
+        let mut project = project(&[0, 1, 2], &[0, 1, 2, 1]);
+        project.delete_label(1, None).unwrap();
+        assert_eq!(box_ids(&project), [0, 2]);
+        assert!(!project.labels.contains_key(&1));
+
+        project.delete_label(2, Some(0)).unwrap();
+        assert_eq!(box_ids(&project), [0, 0]);
+        assert_eq!(project.labels.len(), 1);
+    }
+
+    #[test]
+    fn moved_boxes_keep_only_attributes_of_their_class() {
+        use crate::attributes::AttributeKind;
+        use crate::attributes::AttributeValue;
+
+        let mut project = project(&[0, 1], &[0, 0]);
+        let spec = |name: &str| AttributeSpec {
+            name: name.to_string(),
+            kind: AttributeKind::Bool,
+        };
+        project
+            .attribute_schema
+            .insert(0, vec![spec("occluded"), spec("parked")]);
+        project.attribute_schema.insert(1, vec![spec("occluded")]);
+        for segment in &mut project.images[0].segments {
+            segment
+                .attributes
+                .insert("occluded".to_string(), AttributeValue::Bool(true));
+            segment
+                .attributes
+                .insert("parked".to_string(), AttributeValue::Bool(true));
+        }
+        project.delete_label(0, Some(1)).unwrap();
+        for segment in &project.images[0].segments {
+            assert_eq!(segment.label_id, 1);
+            assert_eq!(segment.attributes.keys().collect::<Vec<_>>(), ["occluded"]);
+        }
+    }
+
+    #[test]
+    fn compact_keeps_order_and_unknown_ids() {
+        let mut project = project(&[3, 7, 9], &[9, 3, 7, 42]);
+        project.compact_label_ids().unwrap();
+        assert_eq!(box_ids(&project), [2, 0, 1, 42]);
+        assert_eq!(name(&project, 0), "class3");
+        assert_eq!(name(&project, 1), "class7");
+        assert_eq!(name(&project, 2), "class9");
+    }
+
+    #[test]
+    fn go_to_name_ignores_empty_query() {
+        let mut project = project(&[0], &[0]);
+        assert!(!project.go_to_name(""));
+        assert!(project.go_to_name("A.P"));
+        assert!(!project.go_to_name("b.png"));
+    }
+
+    #[test]
+    fn remap_refused_while_loading() {
+        let mut project = project(&[0, 1], &[0, 1]);
+        project.images[0].labels_loaded = false;
+        assert!(project.compact_label_ids().is_err());
+        assert!(project.delete_label(0, None).is_err());
+        assert!(project.swap_label_ids(0, 1).is_err());
+        assert_eq!(box_ids(&project), [0, 1]);
+        assert_eq!(project.labels.len(), 2);
     }
 }
@@ -0,0 +1,110 @@
+use crate::project::Segment;
+
+use egui::Color32;
+use egui::FontId;
+
+pub struct BoxStyle {
+    pub stroke_width: f32,
+    pub fill_opacity: f32,
+    pub show_tags: bool,
+    pub show_class_id: bool,
+    pub show_confidence: bool,
+    pub show_track_id: bool,
+    /// Fade out unselected boxes while something is selected.
+    pub dim_unselected: bool,
+}
+
+impl Default for BoxStyle {
+    fn default() -> Self {
+        Self {
+            stroke_width: 1.5,
+            fill_opacity: 0.1,
+            show_tags: true,
+            show_class_id: false,
+            show_confidence: true,
+            show_track_id: true,
+            dim_unselected: true,
+        }
+    }
+}
+
+impl BoxStyle {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.add(egui::Slider::new(&mut self.stroke_width, 0.5..=6.0).text("Stroke"));
+        ui.add(egui::Slider::new(&mut self.fill_opacity, 0.0..=1.0).text("Fill"));
+        ui.checkbox(&mut self.show_tags, "Tags");
+        ui.add_enabled_ui(self.show_tags, |ui| {
+            ui.checkbox(&mut self.show_class_id, "Class id");
+            ui.checkbox(&mut self.show_confidence, "Confidence");
+            ui.checkbox(&mut self.show_track_id, "Track id");
+        });
+        ui.checkbox(&mut self.dim_unselected, "Dim unselected");
+    }
+
+    /// Text of the tag drawn above a box.
+    pub fn caption(&self, name: &str, segment: &Segment) -> String {
+        let mut caption = if self.show_class_id {
+            format!("{} {}", segment.label_id, name)
+        } else {
+            name.to_string()
+        };
+        if self.show_track_id
+            && let Some(track_id) = segment.track_id
+        {
+            caption.push_str(&format!(" #{}", track_id));
+        }
+        if self.show_confidence
+            && let Some(confidence) = segment.confidence
+        {
+            caption.push_str(&format!(" {:.2}", confidence));
+        }
+        caption
+    }
+
+    pub fn draw(
+        &self,
+        painter: &egui::Painter,
+        rect: egui::Rect,
+        color: Color32,
+        caption: &str,
+        dimmed: bool,
+    ) {
+        let color = if dimmed {
+            color.gamma_multiply(0.35)
+        } else {
+            color
+        };
+        painter.rect(
+            rect,
+            0.0,
+            color.gamma_multiply(self.fill_opacity),
+            (self.stroke_width, color),
+            egui::StrokeKind::Middle,
+        );
+
+        if self.show_tags && !caption.is_empty() {
+            let galley = painter.layout_no_wrap(
+                caption.to_string(),
+                FontId::proportional(12.0),
+                text_color(color),
+            );
+            let tag = egui::Rect::from_min_size(
+                rect.left_top() - egui::vec2(self.stroke_width / 2.0, galley.size().y),
+                galley.size() + egui::vec2(4.0, 0.0),
+            );
+            painter.rect_filled(tag, 0.0, color);
+            painter.galley(tag.min + egui::vec2(2.0, 0.0), galley, Color32::BLACK);
+        }
+    }
+}
+
+/// Black or white, whichever reads better on `background`.
+fn text_color(background: Color32) -> Color32 {
+    let [r, g, b, _] = background.to_array();
+    let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    if luma > 140.0 {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
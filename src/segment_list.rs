@@ -0,0 +1,141 @@
+use crate::app::App;
+use egui_extras::Column;
+
+#[derive(Default, PartialEq, Eq, Copy, Clone)]
+pub enum SegmentSort {
+    #[default]
+    Index,
+    Class,
+    Width,
+    Height,
+    Area,
+}
+
+impl App {
+    pub fn segment_list(&mut self, ctx: &egui::Context) {
+        if !self.show_segment_list {
+            return;
+        }
+        let Some(project) = &mut self.project else {
+            return;
+        };
+        let Some(image) = project.images.get_mut(project.image_index) else {
+            return;
+        };
+
+        let pixel_size = image.pixel_size.unwrap_or(egui::Vec2::splat(1.0));
+        let mut order: Vec<usize> = (0..image.segments.len()).collect();
+        let segments = &image.segments;
+        match self.segment_sort {
+            SegmentSort::Index => {}
+            SegmentSort::Class => order.sort_by_key(|&i| segments[i].label_id),
+            SegmentSort::Width => {
+                order.sort_by(|&a, &b| segments[a].size.x.total_cmp(&segments[b].size.x))
+            }
+            SegmentSort::Height => {
+                order.sort_by(|&a, &b| segments[a].size.y.total_cmp(&segments[b].size.y))
+            }
+            SegmentSort::Area => order.sort_by(|&a, &b| {
+                let area = |i: usize| segments[i].size.x * segments[i].size.y;
+                area(a).total_cmp(&area(b))
+            }),
+        }
+
+        let mut label_ids: Vec<u32> = project.labels.keys().copied().collect();
+        label_ids.sort();
+
+        let mut delete = None;
+        let mut relabelled = false;
+        egui::SidePanel::left("segmentlist")
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                ui.heading("Boxes");
+                let mut sort = |ui: &mut egui::Ui, sort: SegmentSort, text: &str| {
+                    if ui
+                        .selectable_label(self.segment_sort == sort, text)
+                        .clicked()
+                    {
+                        self.segment_sort = sort;
+                    }
+                };
+                egui_extras::TableBuilder::new(ui)
+                    .striped(true)
+                    .sense(egui::Sense::click())
+                    .column(Column::auto())
+                    .column(Column::remainder())
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .column(Column::auto())
+                    .header(18.0, |mut header| {
+                        header.col(|ui| sort(ui, SegmentSort::Index, "#"));
+                        header.col(|ui| sort(ui, SegmentSort::Class, "Class"));
+                        header.col(|ui| {
+                            ui.horizontal(|ui| {
+                                sort(ui, SegmentSort::Width, "W");
+                                sort(ui, SegmentSort::Height, "H");
+                            });
+                        });
+                        header.col(|ui| sort(ui, SegmentSort::Area, "Area"));
+                        header.col(|_| {});
+                    })
+                    .body(|mut body| {
+                        for &i in &order {
+                            let segment = &mut image.segments[i];
+                            let size = segment.size * pixel_size;
+                            body.row(20.0, |mut row| {
+                                row.set_selected(segment.selected);
+                                row.col(|ui| {
+                                    ui.label(i.to_string());
+                                });
+                                row.col(|ui| {
+                                    let name = |id: u32| {
+                                        project
+                                            .labels
+                                            .get(&id)
+                                            .map_or(id.to_string(), |l| l.name.clone())
+                                    };
+                                    let mut label_id = segment.label_id;
+                                    egui::ComboBox::from_id_salt(("segmentclass", i))
+                                        .selected_text(name(label_id))
+                                        .show_ui(ui, |ui| {
+                                            for &id in &label_ids {
+                                                ui.selectable_value(&mut label_id, id, name(id));
+                                            }
+                                        });
+                                    if label_id != segment.label_id {
+                                        segment.set_label(label_id, &project.attribute_schema);
+                                        relabelled = true;
+                                    }
+                                });
+                                row.col(|ui| {
+                                    ui.label(format!("{:.0}×{:.0}", size.x, size.y));
+                                });
+                                row.col(|ui| {
+                                    ui.label(format!("{:.0}", size.x * size.y));
+                                });
+                                row.col(|ui| {
+                                    let icon = if segment.hidden { "" } else { "" };
+                                    if ui.small_button(icon).on_hover_text("Hide").clicked() {
+                                        segment.hidden ^= true;
+                                    }
+                                    if ui.small_button("").on_hover_text("Delete").clicked() {
+                                        delete = Some(i);
+                                    }
+                                });
+                                if row.response().clicked() {
+                                    segment.selected ^= true;
+                                }
+                            });
+                        }
+                    });
+            });
+
+        if let Some(i) = delete {
+            image.segments.remove(i);
+            project.edit_drag = None;
+        }
+        if relabelled || delete.is_some() {
+            project.crop_boxes = None;
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::attributes::AttributeSpec;
+use crate::colors::Palette;
+use crate::orientation::OrientationPolicy;
+use crate::project::Project;
+use crate::tags::TagGroup;
+
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Project settings that have no place in the YOLO dataset yaml, stored in a
+/// json file next to it.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Sidecar {
+    pub palette: Palette,
+    pub orientation_policy: OrientationPolicy,
+    /// Hex color of each class whose color was picked by hand.
+    pub colors: BTreeMap<u32, String>,
+    /// Per image notes by file name, for images that have any.
+    pub images: BTreeMap<String, ImageNotes>,
+    pub tag_groups: Vec<TagGroup>,
+    /// Box attributes of each class id.
+    pub attribute_schema: BTreeMap<u32, Vec<AttributeSpec>>,
+    /// Class id bound to each digit key, in keyboard order.
+    pub hotkeys: Option<[Option<u32>; 10]>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ImageNotes {
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub flags: BTreeSet<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub note: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Sidecar {
+    pub fn path(yaml_file_path: &Path) -> PathBuf {
+        yaml_file_path.with_extension("labelel.json")
+    }
+
+    /// A missing sidecar is not an error, the project just hasn't been saved
+    /// by labelel yet.
+    pub fn read(yaml_file_path: &Path) -> anyhow::Result<Sidecar> {
+        match fs::read_to_string(Self::path(yaml_file_path)) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Sidecar::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn write(project: &Project) -> anyhow::Result<()> {
+        let sidecar = Sidecar {
+            palette: project.palette,
+            orientation_policy: project.orientation_policy,
+            colors: project
+                .labels
+                .iter()
+                .filter(|(_, label)| label.custom_color)
+                .map(|(&id, label)| (id, label.color.to_hex()))
+                .collect(),
+            images: project
+                .images
+                .iter()
+                .filter(|image| {
+                    !image.flags.is_empty() || !image.note.is_empty() || !image.tags.is_empty()
+                })
+                .map(|image| {
+                    let notes = ImageNotes {
+                        flags: image.flags.clone(),
+                        note: image.note.clone(),
+                        tags: image.tags.clone(),
+                    };
+                    (image.file_name.clone(), notes)
+                })
+                .collect(),
+            tag_groups: project.tag_groups.clone(),
+            attribute_schema: project.attribute_schema.clone(),
+            hotkeys: Some(project.hotkeys),
+        };
+        fs::write(
+            Self::path(&project.yaml_file_path),
+            serde_json::to_string_pretty(&sidecar)?,
+        )?;
+        Ok(())
+    }
+}
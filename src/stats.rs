@@ -0,0 +1,346 @@
+use crate::app::App;
+use crate::csv;
+use crate::project::Image;
+use crate::project::ImageStatus;
+use crate::project::Project;
+
+use egui::ahash::HashMap;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::hash::Hash;
+use std::path::Path;
+
+const BINS: usize = 20;
+
+#[derive(Serialize, Default)]
+pub struct Histogram {
+    pub min: f32,
+    pub max: f32,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    fn new(values: &[f32]) -> Histogram {
+        if values.is_empty() {
+            return Histogram::default();
+        }
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let mut counts = vec![0; BINS];
+        for &value in values {
+            let bin = ((value - min) / (max - min) * BINS as f32) as usize;
+            counts[bin.min(BINS - 1)] += 1;
+        }
+        Histogram { min, max, counts }
+    }
+
+    fn bin_range(&self, bin: usize) -> (f32, f32) {
+        let width = (self.max - self.min) / self.counts.len() as f32;
+        (
+            self.min + width * bin as f32,
+            self.min + width * (bin + 1) as f32,
+        )
+    }
+}
+
+#[derive(Serialize)]
+pub struct ClassStats {
+    pub id: u32,
+    pub name: String,
+    pub boxes: usize,
+    pub images: usize,
+    pub width: Histogram,
+    pub height: Histogram,
+    pub area: Histogram,
+    pub aspect_ratio: Histogram,
+}
+
+#[derive(Serialize)]
+pub struct DatasetStats {
+    pub images: usize,
+    pub boxes: usize,
+    pub unlabelled_images: usize,
+    /// Number of images with a given number of boxes.
+    pub boxes_per_image: BTreeMap<usize, usize>,
+    pub resolutions: BTreeMap<String, usize>,
+    pub classes: Vec<ClassStats>,
+    /// Number of images containing both classes, for each pair of class ids.
+    pub co_occurrence: Vec<(u32, u32, usize)>,
+}
+
+impl DatasetStats {
+    /// Box dimensions are in pixels for images whose size the loader could
+    /// read, and relative to the image otherwise.
+    pub fn compute(project: &Project) -> DatasetStats {
+        let mut boxes_per_image = BTreeMap::new();
+        let mut resolutions = BTreeMap::new();
+        let mut pairs: BTreeMap<(u32, u32), usize> = BTreeMap::new();
+        let mut dims: HashMap<u32, Vec<egui::Vec2>> = HashMap::default();
+        let mut images_with: HashMap<u32, usize> = HashMap::default();
+
+        for image in &project.images {
+            *boxes_per_image.entry(image.segments.len()).or_default() += 1;
+            let pixel_size = image.pixel_size.unwrap_or(egui::Vec2::splat(1.0));
+            if let Some(size) = image.pixel_size {
+                *resolutions
+                    .entry(format!("{}x{}", size.x, size.y))
+                    .or_default() += 1;
+            }
+            for segment in &image.segments {
+                dims.entry(segment.label_id)
+                    .or_default()
+                    .push(segment.size * pixel_size);
+            }
+
+            let mut present: Vec<u32> = image.segments.iter().map(|s| s.label_id).collect();
+            present.sort();
+            present.dedup();
+            for (n, &a) in present.iter().enumerate() {
+                *images_with.entry(a).or_default() += 1;
+                for &b in &present[n + 1..] {
+                    *pairs.entry((a, b)).or_default() += 1;
+                }
+            }
+        }
+
+        let mut label_ids: Vec<u32> = project.labels.keys().copied().collect();
+        label_ids.sort();
+        let classes = label_ids
+            .into_iter()
+            .map(|id| {
+                let dims = dims.remove(&id).unwrap_or_default();
+                let values = |f: fn(&egui::Vec2) -> f32| dims.iter().map(f).collect::<Vec<_>>();
+                ClassStats {
+                    id,
+                    name: project
+                        .labels
+                        .get(&id)
+                        .map_or("?".to_string(), |l| l.name.clone()),
+                    boxes: dims.len(),
+                    images: images_with.get(&id).copied().unwrap_or(0),
+                    width: Histogram::new(&values(|d| d.x)),
+                    height: Histogram::new(&values(|d| d.y)),
+                    area: Histogram::new(&values(|d| d.x * d.y)),
+                    aspect_ratio: Histogram::new(&values(|d| d.x / d.y.max(f32::EPSILON))),
+                }
+            })
+            .collect();
+
+        DatasetStats {
+            images: project.images.len(),
+            boxes: project.images.iter().map(|i| i.segments.len()).sum(),
+            // Images with an empty label file are checked backgrounds.
+            unlabelled_images: project
+                .images
+                .iter()
+                .filter(|image| image.status() == ImageStatus::Unlabelled)
+                .count(),
+            boxes_per_image,
+            resolutions,
+            classes,
+            co_occurrence: pairs.into_iter().map(|((a, b), n)| (a, b, n)).collect(),
+        }
+    }
+
+    /// Writes the statistics as JSON or, for a `.csv` path, as one
+    /// `metric,class,from,to,count` row per histogram bin.
+    pub fn export(&self, path: &Path) -> anyhow::Result<()> {
+        if path.extension().is_some_and(|e| e == "csv") {
+            std::fs::write(path, self.to_csv())?;
+        } else {
+            std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        }
+        Ok(())
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = "metric,class,from,to,count\n".to_string();
+        for (boxes, count) in &self.boxes_per_image {
+            writeln!(csv, "boxes_per_image,,{boxes},{boxes},{count}").unwrap();
+        }
+        for (resolution, count) in &self.resolutions {
+            writeln!(csv, "resolution,,{resolution},{resolution},{count}").unwrap();
+        }
+        for class in &self.classes {
+            for (metric, histogram) in [
+                ("width", &class.width),
+                ("height", &class.height),
+                ("area", &class.area),
+                ("aspect_ratio", &class.aspect_ratio),
+            ] {
+                for (bin, count) in histogram.counts.iter().enumerate() {
+                    let (from, to) = histogram.bin_range(bin);
+                    writeln!(
+                        csv,
+                        "{metric},{},{from},{to},{count}",
+                        csv::field(&class.name)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        for (a, b, count) in &self.co_occurrence {
+            writeln!(csv, "co_occurrence,,{a},{b},{count}").unwrap();
+        }
+        csv
+    }
+}
+
+/// Box count of every class over the given images.
+pub fn count_labels<'a>(images: impl IntoIterator<Item = &'a Image>) -> HashMap<u32, usize> {
+    let mut counts = HashMap::default();
+    for segment in images.into_iter().flat_map(|image| &image.segments) {
+        *counts.entry(segment.label_id).or_default() += 1;
+    }
+    counts
+}
+
+impl App {
+    pub fn stats_window(&mut self, ctx: &egui::Context) {
+        let Some(project) = &mut self.project else {
+            return;
+        };
+        if self.show_stats && self.stats.is_none() {
+            self.stats = Some(DatasetStats::compute(project));
+        }
+
+        let mut refresh = false;
+        let mut export = false;
+        egui::Window::new("Statistics")
+            .open(&mut self.show_stats)
+            .default_size((420.0, 480.0))
+            .show(ctx, |ui| {
+                let Some(stats) = &self.stats else {
+                    return;
+                };
+                ui.horizontal(|ui| {
+                    refresh = ui.button("Refresh").clicked();
+                    export = ui.button("Export").clicked();
+                });
+                ui.label(format!(
+                    "{} images, {} boxes, {} unlabelled",
+                    stats.images, stats.boxes, stats.unlabelled_images
+                ));
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    stats_ui(ui, stats, project);
+                });
+            });
+
+        if refresh {
+            self.stats = Some(DatasetStats::compute(project));
+        }
+        if export
+            && let Some(stats) = &self.stats
+            && let Some(path) = rfd::FileDialog::new()
+                .add_filter("json", &["json"])
+                .add_filter("csv", &["csv"])
+                .save_file()
+            && let Err(err) = stats.export(&path)
+        {
+            self.message_box = Some(format!("{}", err));
+        }
+    }
+}
+
+fn stats_ui(ui: &mut egui::Ui, stats: &DatasetStats, project: &Project) {
+    ui.collapsing("Boxes per image", |ui| {
+        let counts: Vec<_> = stats.boxes_per_image.iter().collect();
+        bars(
+            ui,
+            "boxesperimage",
+            counts
+                .iter()
+                .map(|(boxes, count)| (boxes.to_string(), **count)),
+        );
+    });
+    ui.collapsing("Resolutions", |ui| {
+        egui::Grid::new("resolutions").striped(true).show(ui, |ui| {
+            for (resolution, count) in &stats.resolutions {
+                ui.label(resolution);
+                ui.label(count.to_string());
+                ui.end_row();
+            }
+        });
+    });
+    for class in &stats.classes {
+        let color = project
+            .labels
+            .get(&class.id)
+            .map_or(egui::Color32::GRAY, |l| l.color);
+        let title = egui::RichText::new(format!(
+            "{} ({} boxes in {} images)",
+            class.name, class.boxes, class.images
+        ))
+        .color(color);
+        egui::CollapsingHeader::new(title)
+            .id_salt(("classstats", class.id))
+            .show(ui, |ui| {
+                for (name, histogram) in [
+                    ("Width", &class.width),
+                    ("Height", &class.height),
+                    ("Area", &class.area),
+                    ("Aspect ratio", &class.aspect_ratio),
+                ] {
+                    ui.label(name);
+                    histogram_ui(ui, (class.id, name), histogram);
+                }
+            });
+    }
+    ui.collapsing("Co-occurrence", |ui| {
+        let name = |id: u32| {
+            project
+                .labels
+                .get(&id)
+                .map_or(id.to_string(), |l| l.name.clone())
+        };
+        egui::Grid::new("cooccurrence")
+            .striped(true)
+            .show(ui, |ui| {
+                for &(a, b, count) in &stats.co_occurrence {
+                    ui.label(name(a));
+                    ui.label(name(b));
+                    ui.label(count.to_string());
+                    ui.end_row();
+                }
+            });
+    });
+}
+
+fn histogram_ui(ui: &mut egui::Ui, id_salt: impl Hash, histogram: &Histogram) {
+    bars(
+        ui,
+        id_salt,
+        histogram.counts.iter().enumerate().map(|(bin, &count)| {
+            let (from, to) = histogram.bin_range(bin);
+            (format!("{from:.1} – {to:.1}"), count)
+        }),
+    );
+}
+
+/// Draws a bar chart, with each bar's caption shown on hover. `id_salt` has
+/// to be unique among the charts.
+fn bars(
+    ui: &mut egui::Ui,
+    id_salt: impl Hash,
+    bars: impl ExactSizeIterator<Item = (String, usize)>,
+) {
+    let id = ui.id().with(id_salt);
+    let bars: Vec<_> = bars.collect();
+    let max = bars.iter().map(|b| b.1).max().unwrap_or(0).max(1);
+    let (rect, _) =
+        ui.allocate_exact_size(egui::vec2(ui.available_width(), 64.0), egui::Sense::hover());
+    let width = rect.width() / bars.len().max(1) as f32;
+    let color = ui.visuals().selection.bg_fill;
+    for (n, (caption, count)) in bars.into_iter().enumerate() {
+        let height = rect.height() * count as f32 / max as f32;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(rect.left() + width * n as f32, rect.bottom() - height),
+            egui::pos2(rect.left() + width * (n + 1) as f32 - 1.0, rect.bottom()),
+        );
+        ui.painter().rect_filled(bar, 0.0, color);
+        let column = egui::Rect::from_x_y_ranges(bar.x_range(), rect.y_range());
+        ui.interact(column, id.with(("bar", n)), egui::Sense::hover())
+            .on_hover_text(format!("{caption}: {count}"));
+    }
+}
@@ -0,0 +1,368 @@
+use crate::app::App;
+use crate::csv;
+use crate::project::Project;
+
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Keys that toggle image tags, in the order the tags are listed.
+pub const TAG_KEYS: [egui::Key; 12] = [
+    egui::Key::F1,
+    egui::Key::F2,
+    egui::Key::F3,
+    egui::Key::F4,
+    egui::Key::F5,
+    egui::Key::F6,
+    egui::Key::F7,
+    egui::Key::F8,
+    egui::Key::F9,
+    egui::Key::F10,
+    egui::Key::F11,
+    egui::Key::F12,
+];
+
+/// A set of whole image labels, such as weather or scene type.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TagGroup {
+    pub name: String,
+    /// Whether an image can have more than one tag of the group.
+    pub multiple: bool,
+    pub tags: Vec<String>,
+}
+
+/// Input fields of the tag group editor.
+pub struct TagEditor {
+    group_name: String,
+    multiple: bool,
+    group: usize,
+    tag: String,
+    /// Group exported as classes.
+    export_group: usize,
+    /// Share of each class exported for validation, in percent.
+    val_percent: u32,
+}
+
+impl Default for TagEditor {
+    fn default() -> Self {
+        Self {
+            group_name: String::new(),
+            multiple: false,
+            group: 0,
+            tag: String::new(),
+            export_group: 0,
+            val_percent: 20,
+        }
+    }
+}
+
+/// Checks that a tag can be used as a folder name on any platform.
+pub fn check_tag_name(name: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(!name.trim().is_empty(), "Tag names can't be empty");
+    anyhow::ensure!(
+        name != "." && name != "..",
+        "\"{}\" can't be used as a tag name",
+        name
+    );
+    let invalid = |c: char| c.is_control() || "/\\:*?\"<>|".contains(c);
+    anyhow::ensure!(
+        !name.contains(invalid),
+        "Tag names can't contain path separators or any of : * ? \" < > |, found \"{}\"",
+        name
+    );
+    Ok(())
+}
+
+/// The tag toggled by each of [`TAG_KEYS`], as group and tag index.
+pub fn tag_keys(project: &Project) -> Vec<(usize, usize)> {
+    project
+        .tag_groups
+        .iter()
+        .enumerate()
+        .flat_map(|(g, group)| (0..group.tags.len()).map(move |t| (g, t)))
+        .take(TAG_KEYS.len())
+        .collect()
+}
+
+/// Tags of the current image, in the sidebar.
+pub fn image_tags(ui: &mut egui::Ui, project: &mut Project) {
+    let keys = tag_keys(project);
+    let mut toggle = None;
+    for (g, group) in project.tag_groups.iter().enumerate() {
+        let Some(image) = project.images.get(project.image_index) else {
+            return;
+        };
+        ui.label(&group.name);
+        ui.horizontal_wrapped(|ui| {
+            for (t, tag) in group.tags.iter().enumerate() {
+                let set = image
+                    .tags
+                    .get(&group.name)
+                    .is_some_and(|tags| tags.contains(tag));
+                let mut res = ui.selectable_label(set, tag);
+                if let Some(key) = keys.iter().position(|&k| k == (g, t)) {
+                    res = res.on_hover_text(TAG_KEYS[key].name());
+                }
+                if res.clicked() {
+                    toggle = Some((g, t));
+                }
+            }
+        });
+    }
+    if let Some((g, t)) = toggle {
+        project.toggle_tag(g, t);
+    }
+}
+
+/// Writes the images tagged in a single choice group into an Ultralytics
+/// classification layout, `train/<tag>/<image>` and `val/<tag>/<image>`, with
+/// `val_percent` of each tag's images spread evenly into `val`. Files are hard
+/// linked where possible. Returns the number of images exported.
+pub fn export_classification(
+    project: &Project,
+    group: usize,
+    val_percent: u32,
+    dir: &Path,
+) -> anyhow::Result<usize> {
+    let Some(group) = project.tag_groups.get(group) else {
+        anyhow::bail!("No tag group to export");
+    };
+    // The group may have allowed several tags before it was made single choice.
+    let ambiguous = project
+        .images
+        .iter()
+        .filter(|image| {
+            image
+                .tags
+                .get(&group.name)
+                .is_some_and(|tags| tags.len() > 1)
+        })
+        .count();
+    anyhow::ensure!(
+        ambiguous == 0,
+        "{} images have more than one \"{}\" tag",
+        ambiguous,
+        group.name
+    );
+
+    let ratio = val_percent as f32 / 100.0;
+    let mut per_tag: HashMap<&str, usize> = HashMap::new();
+    let mut count = 0;
+    for image in &project.images {
+        let Some(tag) = image.tags.get(&group.name).and_then(|tags| tags.first()) else {
+            continue;
+        };
+        let n = per_tag.entry(tag).or_default();
+        let split = if ((*n + 1) as f32 * ratio).floor() > (*n as f32 * ratio).floor() {
+            "val"
+        } else {
+            "train"
+        };
+        *n += 1;
+        // Tags may have been written into the sidecar by hand.
+        check_tag_name(tag)?;
+        let class_dir = dir.join(split).join(tag);
+        fs::create_dir_all(&class_dir)?;
+        let target = class_dir.join(&image.file_name);
+        if fs::hard_link(&image.file_path, &target).is_err() {
+            fs::copy(&image.file_path, &target)?;
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// One row per image and one column per group, tags joined by `;`.
+pub fn export_csv(project: &Project, path: &Path) -> anyhow::Result<()> {
+    let mut csv = "image".to_string();
+    for group in &project.tag_groups {
+        write!(csv, ",{}", csv::field(&group.name))?;
+    }
+    csv.push('\n');
+    for image in &project.images {
+        csv.push_str(&csv::field(&image.file_name));
+        for group in &project.tag_groups {
+            let tags: Vec<&str> = image
+                .tags
+                .get(&group.name)
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect();
+            write!(csv, ",{}", csv::field(&tags.join(";")))?;
+        }
+        csv.push('\n');
+    }
+    fs::write(path, csv)?;
+    Ok(())
+}
+
+enum TagAction {
+    AddGroup,
+    RemoveGroup(usize),
+    AddTag,
+    RemoveTag(usize, usize),
+    ExportFolders,
+    ExportCsv,
+}
+
+impl App {
+    pub fn tag_editor_window(&mut self, ctx: &egui::Context) {
+        let Some(project) = &mut self.project else {
+            return;
+        };
+        let editor = &mut self.tag_editor;
+
+        let mut action = None;
+        egui::Window::new("Image tags")
+            .open(&mut self.show_tag_editor)
+            .show(ctx, |ui| {
+                egui::Grid::new("taggroups").striped(true).show(ui, |ui| {
+                    for (g, group) in project.tag_groups.iter_mut().enumerate() {
+                        ui.strong(&group.name);
+                        ui.checkbox(&mut group.multiple, "Multiple");
+                        ui.horizontal_wrapped(|ui| {
+                            for (t, tag) in group.tags.iter().enumerate() {
+                                if ui
+                                    .button(format!("{} ×", tag))
+                                    .on_hover_text("Remove")
+                                    .clicked()
+                                {
+                                    action = Some(TagAction::RemoveTag(g, t));
+                                }
+                            }
+                        });
+                        if ui.button("Remove group").clicked() {
+                            action = Some(TagAction::RemoveGroup(g));
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut editor.group_name)
+                            .hint_text("Group")
+                            .desired_width(120.0),
+                    );
+                    ui.checkbox(&mut editor.multiple, "Multiple");
+                    if ui.button("Add group").clicked() && !editor.group_name.is_empty() {
+                        action = Some(TagAction::AddGroup);
+                    }
+                });
+                let group_name = |g: usize| project.tag_groups.get(g).map_or("-", |g| &g.name);
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("taggroup")
+                        .selected_text(group_name(editor.group))
+                        .show_ui(ui, |ui| {
+                            for g in 0..project.tag_groups.len() {
+                                ui.selectable_value(&mut editor.group, g, group_name(g));
+                            }
+                        });
+                    ui.add(
+                        egui::TextEdit::singleline(&mut editor.tag)
+                            .hint_text("Tag")
+                            .desired_width(120.0),
+                    );
+                    if ui.button("Add tag").clicked()
+                        && !editor.tag.is_empty()
+                        && editor.group < project.tag_groups.len()
+                    {
+                        action = Some(TagAction::AddTag);
+                    }
+                });
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("exportgroup")
+                        .selected_text(group_name(editor.export_group))
+                        .show_ui(ui, |ui| {
+                            for (g, group) in project.tag_groups.iter().enumerate() {
+                                if !group.multiple {
+                                    ui.selectable_value(&mut editor.export_group, g, &group.name);
+                                }
+                            }
+                        });
+                    let exportable = project
+                        .tag_groups
+                        .get(editor.export_group)
+                        .is_some_and(|group| !group.multiple);
+                    ui.add(
+                        egui::DragValue::new(&mut editor.val_percent)
+                            .range(0..=90)
+                            .suffix("% val"),
+                    );
+                    if ui
+                        .add_enabled(exportable, egui::Button::new("Export folders…"))
+                        .on_hover_text("Ultralytics classification layout")
+                        .clicked()
+                    {
+                        action = Some(TagAction::ExportFolders);
+                    }
+                    if ui.button("Export CSV…").clicked() {
+                        action = Some(TagAction::ExportCsv);
+                    }
+                });
+            });
+
+        let result = match action {
+            Some(TagAction::AddGroup) => {
+                project.add_tag_group(&editor.group_name, editor.multiple);
+                editor.group_name.clear();
+                Ok(())
+            }
+            Some(TagAction::RemoveGroup(g)) => {
+                project.remove_tag_group(g);
+                Ok(())
+            }
+            Some(TagAction::AddTag) => project
+                .add_tag(editor.group, &editor.tag)
+                .map(|()| editor.tag.clear()),
+            Some(TagAction::RemoveTag(g, t)) => {
+                project.remove_tag(g, t);
+                Ok(())
+            }
+            Some(TagAction::ExportFolders) => match rfd::FileDialog::new().pick_folder() {
+                Some(dir) => {
+                    export_classification(project, editor.export_group, editor.val_percent, &dir)
+                        .map(|count| {
+                            self.message_box = Some(format!("Exported {} images", count));
+                        })
+                }
+                None => Ok(()),
+            },
+            Some(TagAction::ExportCsv) => match rfd::FileDialog::new()
+                .add_filter("csv", &["csv"])
+                .save_file()
+            {
+                Some(path) => export_csv(project, &path),
+                None => Ok(()),
+            },
+            None => Ok(()),
+        };
+        if let Err(err) = result {
+            self.message_box = Some(format!("{}", err));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_names_are_single_path_components() {
+        for name in ["sunny", "light rain", "night.v2", "ünïcode"] {
+            assert!(check_tag_name(name).is_ok(), "{}", name);
+        }
+        for name in [
+            "", " ", ".", "..", "a/b", "../up", "a\\b", "c:", "a*", "tab\t",
+        ] {
+            assert!(check_tag_name(name).is_err(), "{:?}", name);
+        }
+    }
+}
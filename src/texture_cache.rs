@@ -0,0 +1,98 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::sync::mpsc;
+
+/// Textures made from images decoded on the thread pool, kept only while they
+/// are asked for every frame. `K` identifies a texture and `J` a job, which
+/// can make several textures at once.
+pub struct TextureCache<K, T, J, R> {
+    /// `None` for textures whose image failed to decode.
+    textures: HashMap<K, Option<T>>,
+    /// Textures asked for this frame, everything else is evicted.
+    used: HashSet<K>,
+    pending: HashSet<J>,
+    sender: mpsc::Sender<(J, R)>,
+    receiver: mpsc::Receiver<(J, R)>,
+}
+
+impl<K, T, J, R> Default for TextureCache<K, T, J, R> {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            textures: HashMap::new(),
+            used: HashSet::new(),
+            pending: HashSet::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl<K, T, J, R> TextureCache<K, T, J, R>
+where
+    K: Eq + Hash + Clone,
+    J: Eq + Hash + Clone + Send + 'static,
+    R: Send + 'static,
+{
+    /// Turns the results of the jobs finished since the last frame into
+    /// textures.
+    pub fn receive<I>(&mut self, mut upload: impl FnMut(&J, R) -> I)
+    where
+        I: IntoIterator<Item = (K, Option<T>)>,
+    {
+        while let Ok((job, result)) = self.receiver.try_recv() {
+            self.pending.remove(&job);
+            self.textures.extend(upload(&job, result));
+        }
+    }
+
+    /// Whether the texture was made, or failed to be.
+    pub fn contains(&self, key: &K) -> bool {
+        self.textures.contains_key(key)
+    }
+
+    /// The texture for `key` if it's ready, keeping it for another frame.
+    pub fn get(&mut self, key: &K) -> Option<&T> {
+        self.used.insert(key.clone());
+        self.textures.get(key).and_then(Option::as_ref)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_pending<Q>(&self, job: &Q) -> bool
+    where
+        J: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.pending.contains(job)
+    }
+
+    /// Runs `work` on the thread pool unless the same job is already running.
+    pub fn spawn(
+        &mut self,
+        ctx: &egui::Context,
+        job: J,
+        work: impl FnOnce(&J) -> R + Send + 'static,
+    ) {
+        if !self.pending.insert(job.clone()) {
+            return;
+        }
+        let sender = self.sender.clone();
+        let ctx = ctx.clone();
+        rayon::spawn(move || {
+            let result = work(&job);
+            let _ = sender.send((job, result));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Drops the textures that weren't asked for this frame.
+    pub fn evict(&mut self) {
+        self.textures.retain(|key, _| self.used.contains(key));
+        self.used.clear();
+    }
+}
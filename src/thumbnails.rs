@@ -0,0 +1,161 @@
+use crate::texture_cache::TextureCache;
+
+use image::DynamicImage;
+use image::metadata::Orientation;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Bounds of a thumbnail, large enough for the biggest gallery tiles.
+const THUMBNAIL_SIZE: (u32, u32) = (256, 256);
+/// Upper bound on thumbnails being made at once, so fast scrolling doesn't
+/// queue up work for images long out of view.
+const MAX_PENDING: usize = 32;
+/// Size the disk cache is pruned down to on startup.
+const MAX_CACHE_BYTES: u64 = 512 << 20;
+
+/// An image and the orientation it's shown in, which isn't known until its
+/// labels are read.
+type Key = (PathBuf, Orientation);
+
+/// Downscaled images for the timeline. They are made on the thread pool,
+/// cached on disk and only kept as textures while in view.
+pub struct ThumbnailCache {
+    dir: PathBuf,
+    cache: TextureCache<Key, egui::TextureHandle, Key, Option<egui::ColorImage>>,
+}
+
+impl Default for ThumbnailCache {
+    fn default() -> Self {
+        let dir = std::env::temp_dir().join("labelel-thumbnails");
+        let prune_dir = dir.clone();
+        rayon::spawn(move || {
+            // A missing cache has nothing to prune.
+            if let Err(err) = prune(&prune_dir)
+                && err.kind() != std::io::ErrorKind::NotFound
+            {
+                println!("{}", err);
+            }
+        });
+        Self {
+            dir,
+            cache: TextureCache::default(),
+        }
+    }
+}
+
+impl ThumbnailCache {
+    /// Uploads the thumbnails finished since the last frame.
+    pub fn receive(&mut self, ctx: &egui::Context) {
+        self.cache.receive(|key, image| {
+            let texture = image.map(|image| {
+                ctx.load_texture(key.0.to_string_lossy(), image, egui::TextureOptions::LINEAR)
+            });
+            [(key.clone(), texture)]
+        });
+    }
+
+    /// The thumbnail of `path`, queueing it up if it isn't ready yet.
+    pub fn get(
+        &mut self,
+        ctx: &egui::Context,
+        path: &Path,
+        orientation: Orientation,
+    ) -> Option<&egui::TextureHandle> {
+        let key = (path.to_path_buf(), orientation);
+        if !self.cache.contains(&key) && self.cache.pending() < MAX_PENDING {
+            let dir = self.dir.clone();
+            self.cache.spawn(ctx, key.clone(), move |key| {
+                thumbnail(&dir, &key.0, key.1)
+                    .inspect_err(|err| println!("{}: {}", key.0.display(), err))
+                    .ok()
+            });
+        }
+        self.cache.get(&key)
+    }
+
+    /// Drops the textures of thumbnails that weren't asked for this frame.
+    pub fn evict(&mut self) {
+        self.cache.evict();
+    }
+}
+
+/// Loads the thumbnail of `path` from the disk cache, making it first if the
+/// image is new or has changed since.
+fn thumbnail(
+    dir: &Path,
+    path: &Path,
+    orientation: Orientation,
+) -> anyhow::Result<egui::ColorImage> {
+    let modified = fs::metadata(path)?
+        .modified()?
+        .duration_since(SystemTime::UNIX_EPOCH)?;
+    let mut key = path.as_os_str().as_encoded_bytes().to_vec();
+    key.extend(THUMBNAIL_SIZE.0.to_le_bytes());
+    key.extend(THUMBNAIL_SIZE.1.to_le_bytes());
+    key.extend(modified.as_secs().to_le_bytes());
+    key.extend(modified.subsec_nanos().to_le_bytes());
+    key.push(orientation.to_exif());
+    let cached = dir.join(format!("{:016x}.png", fnv1a(&key)));
+
+    let image = match image::open(&cached) {
+        Ok(image) => {
+            // Marks it as recently used for pruning.
+            let _ = fs::File::options()
+                .write(true)
+                .open(&cached)
+                .and_then(|file| file.set_modified(SystemTime::now()));
+            image
+        }
+        Err(_) => {
+            let mut image = image::open(path)?;
+            image.apply_orientation(orientation);
+            let (width, height) = THUMBNAIL_SIZE;
+            let image = DynamicImage::ImageRgba8(image.thumbnail(width, height).to_rgba8());
+            // Not being able to cache only costs time.
+            if let Err(err) = fs::create_dir_all(dir) {
+                println!("{}", err);
+            } else if let Err(err) = image.save(&cached) {
+                println!("{}", err);
+            }
+            image
+        }
+    };
+    let rgba = image.to_rgba8();
+    Ok(egui::ColorImage::from_rgba_unmultiplied(
+        [rgba.width() as usize, rgba.height() as usize],
+        rgba.as_raw(),
+    ))
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher` it stays the same across Rust
+/// releases, which would otherwise orphan every cached file.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Deletes the least recently used thumbnails until the cache fits in
+/// [`MAX_CACHE_BYTES`].
+fn prune(dir: &Path) -> std::io::Result<()> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            files.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+    }
+    let mut total: u64 = files.iter().map(|file| file.1).sum();
+    files.sort();
+    for (_, len, path) in files {
+        if total <= MAX_CACHE_BYTES {
+            break;
+        }
+        fs::remove_file(path)?;
+        total -= len;
+    }
+    Ok(())
+}
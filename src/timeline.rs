@@ -0,0 +1,252 @@
+use crate::app::App;
+use crate::app::fun_name;
+use crate::project::ImageStatus;
+use crate::project::Project;
+use crate::thumbnails::ThumbnailCache;
+
+use egui::Color32;
+use egui::FontId;
+use egui::Sense;
+
+const TILE_SIZE: egui::Vec2 = egui::vec2(64.0, 36.0);
+
+/// Batch edits of the picked images, confirmed in a modal first.
+#[derive(PartialEq, Eq, Copy, Clone)]
+pub enum PickedAction {
+    CopyBoxes,
+    ClearBoxes,
+}
+
+impl App {
+    /// Filmstrip of every image. Only the thumbnails in view are laid out, so
+    /// it stays cheap for very large datasets.
+    pub fn timeline(&mut self, ctx: &egui::Context) {
+        let loading = self.loader.is_some();
+        egui::TopBottomPanel::bottom("timeline").show(ctx, |ui| {
+            let Some(project) = &mut self.project else {
+                return;
+            };
+
+            if !project.picked_images.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} picked", project.picked_images.len()));
+                    // Images whose labels are still being read would get them back.
+                    ui.add_enabled_ui(!loading, |ui| {
+                        if ui
+                            .button("Copy boxes here")
+                            .on_hover_text(
+                                "Replace their boxes with the boxes of the current image",
+                            )
+                            .clicked()
+                        {
+                            project.picked_action_modal = Some(PickedAction::CopyBoxes);
+                        }
+                        if ui.button("Clear boxes").clicked() {
+                            project.picked_action_modal = Some(PickedAction::ClearBoxes);
+                        }
+                    });
+                    if ui.button("Unpick").clicked() {
+                        project.picked_images.clear();
+                    }
+                });
+            }
+
+            let stride = TILE_SIZE.x + ui.spacing().item_spacing.x;
+            let mut scroll = egui::ScrollArea::horizontal().auto_shrink([false, true]);
+            // Bring the current image into view when it was changed elsewhere.
+            if self.timeline_follow != Some(project.image_index)
+                && let Some(position) = project.position()
+            {
+                self.timeline_follow = Some(project.image_index);
+                let center = position as f32 * stride + TILE_SIZE.x / 2.0;
+                scroll =
+                    scroll.horizontal_scroll_offset((center - ui.available_width() / 2.0).max(0.0));
+            }
+
+            let mut clicked = None;
+            scroll.show_viewport(ui, |ui, viewport| {
+                let origin = ui.max_rect().min;
+                ui.set_min_size(egui::vec2(project.order.len() as f32 * stride, TILE_SIZE.y));
+                let first = (viewport.min.x / stride).floor().max(0.0) as usize;
+                let last = ((viewport.max.x / stride).ceil() as usize).min(project.order.len());
+                // The offset can be past the end for a frame after the list shrinks.
+                let first = first.min(last);
+
+                for (position, &i) in project.order[first..last].iter().enumerate() {
+                    let slot = egui::Rect::from_min_size(
+                        origin + egui::vec2((first + position) as f32 * stride, 0.0),
+                        TILE_SIZE,
+                    );
+                    let res = ui
+                        .interact(slot, ui.id().with(("thumbnail", i)), Sense::CLICK)
+                        .on_hover_text(&project.images[i].file_name);
+                    thumbnail_tile(ui, &mut self.thumbnails, project, i, slot);
+
+                    if res.clicked() {
+                        clicked = Some(i);
+                    }
+                }
+            });
+
+            if let Some(i) = clicked
+                && !project.pick_image(i, ui.input(|i| i.modifiers))
+            {
+                project.picked_images.clear();
+                project.pick_anchor = Some(i);
+                project.image_index = i;
+                self.timeline_follow = Some(i);
+            }
+        });
+
+        self.picked_action_modal(ctx);
+    }
+
+    fn picked_action_modal(&mut self, ctx: &egui::Context) {
+        let Some(project) = &mut self.project else {
+            return;
+        };
+        let Some(action) = project.picked_action_modal else {
+            return;
+        };
+
+        let question = match action {
+            PickedAction::CopyBoxes => {
+                let count = project
+                    .picked_images
+                    .iter()
+                    .filter(|&&i| i != project.image_index)
+                    .count();
+                format!(
+                    "Replace the boxes of {} images with the {} boxes of this one?",
+                    count,
+                    project
+                        .images
+                        .get(project.image_index)
+                        .map_or(0, |image| image.segments.len())
+                )
+            }
+            PickedAction::ClearBoxes => format!(
+                "Delete every box of {} images?",
+                project.picked_images.len()
+            ),
+        };
+
+        let clicked = egui::Modal::new("pickedaction".into())
+            .show(ctx, |ui| {
+                ui.vertical_centered_justified(|ui| {
+                    ui.label(question);
+                    ui.columns(2, |uis| {
+                        if uis[0].button("Yes").clicked() {
+                            Some(true)
+                        } else if uis[1].button("Cancel").clicked() {
+                            Some(false)
+                        } else {
+                            None
+                        }
+                    })
+                })
+                .inner
+            })
+            .inner;
+
+        match clicked {
+            Some(true) => {
+                project.picked_action_modal = None;
+                if self.loader.is_none() {
+                    match action {
+                        PickedAction::CopyBoxes => project.copy_segments_to_picked(),
+                        PickedAction::ClearBoxes => project.clear_picked_segments(),
+                    }
+                }
+            }
+            Some(false) => project.picked_action_modal = None,
+            None => {}
+        }
+    }
+}
+
+/// Draws the thumbnail of an image with its boxes and badges into `slot`.
+pub fn thumbnail_tile(
+    ui: &egui::Ui,
+    thumbnails: &mut ThumbnailCache,
+    project: &Project,
+    index: usize,
+    slot: egui::Rect,
+) {
+    let image = &project.images[index];
+    let painter = ui.painter_at(slot);
+    let thumbnail = thumbnails.get(ui.ctx(), &image.file_path, image.orientation);
+    let image_rect = match thumbnail {
+        Some(texture) => {
+            let size = texture.size_vec2();
+            let rect =
+                egui::Rect::from_center_size(slot.center(), size * (slot.size() / size).min_elem());
+            painter.image(
+                texture.id(),
+                rect,
+                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                Color32::WHITE,
+            );
+            rect
+        }
+        None => {
+            painter.rect_filled(slot, 0.0, ui.visuals().faint_bg_color);
+            slot
+        }
+    };
+
+    for segment in &image.segments {
+        if segment.hidden || !project.label_visible(segment.label_id) {
+            continue;
+        }
+        let color = project
+            .labels
+            .get(&segment.label_id)
+            .map_or(Color32::WHITE, |l| l.color);
+        painter.rect_stroke(
+            fun_name(image_rect, segment),
+            0.0,
+            (1.0, color),
+            egui::StrokeKind::Middle,
+        );
+    }
+
+    let status_color = match image.status() {
+        ImageStatus::Unlabelled => Color32::GRAY,
+        ImageStatus::Background => Color32::LIGHT_BLUE,
+        ImageStatus::Labelled => Color32::GREEN,
+    };
+    painter.circle_filled(slot.left_top() + egui::vec2(5.0, 5.0), 3.0, status_color);
+    if !image.flags.is_empty() {
+        painter.circle_filled(
+            slot.left_bottom() + egui::vec2(5.0, -5.0),
+            3.0,
+            Color32::GOLD,
+        );
+    }
+    if !image.segments.is_empty() {
+        let galley = painter.layout_no_wrap(
+            image.segments.len().to_string(),
+            FontId::proportional(10.0),
+            Color32::WHITE,
+        );
+        let badge = egui::Rect::from_min_size(
+            slot.right_top() - egui::vec2(galley.size().x + 4.0, 0.0),
+            galley.size() + egui::vec2(4.0, 0.0),
+        );
+        painter.rect_filled(badge, 2.0, Color32::from_black_alpha(180));
+        painter.galley(badge.min + egui::vec2(2.0, 0.0), galley, Color32::WHITE);
+    }
+
+    if project.picked_images.contains(&index) {
+        painter.rect_stroke(
+            slot,
+            0.0,
+            ui.visuals().selection.stroke,
+            egui::StrokeKind::Inside,
+        );
+    }
+    if project.image_index == index {
+        painter.rect_stroke(slot, 0.0, (2.0, Color32::RED), egui::StrokeKind::Inside);
+    }
+}
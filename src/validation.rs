@@ -0,0 +1,121 @@
+use crate::project::Image;
+use crate::project::Label;
+
+use egui::ahash::HashMap;
+
+/// How far a box may stick out of the image before it counts as a problem,
+/// to allow for rounding in label files.
+const TOLERANCE: f32 = 1e-3;
+/// Boxes of one class that overlap more than this are likely duplicates.
+const DUPLICATE_IOU: f32 = 0.95;
+
+/// Things in an image's labels that are almost certainly mistakes.
+pub fn problems(image: &Image, labels: &HashMap<u32, Label>) -> Vec<String> {
+    let mut problems = vec![];
+    let bounds = egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1.0, 1.0)).expand(TOLERANCE);
+    let rects: Vec<egui::Rect> = image
+        .segments
+        .iter()
+        .map(|s| egui::Rect::from_center_size(s.center, s.size))
+        .collect();
+    // Less than a pixel, where the image size is known.
+    let min_size = image.pixel_size.map_or(egui::Vec2::splat(1e-4), |size| {
+        egui::Vec2::splat(1.0) / size
+    });
+    for (i, segment) in image.segments.iter().enumerate() {
+        if !labels.contains_key(&segment.label_id) {
+            problems.push(format!("Box {}: unknown class {}", i, segment.label_id));
+        }
+        if segment.size.x < min_size.x || segment.size.y < min_size.y {
+            problems.push(format!("Box {}: empty", i));
+        }
+        if !bounds.contains_rect(rects[i]) {
+            problems.push(format!("Box {}: outside the image", i));
+        }
+        for j in 0..i {
+            if image.segments[j].label_id == segment.label_id
+                && iou(rects[i], rects[j]) > DUPLICATE_IOU
+            {
+                problems.push(format!("Boxes {} and {}: duplicates", j, i));
+            }
+        }
+    }
+    problems
+}
+
+fn iou(a: egui::Rect, b: egui::Rect) -> f32 {
+    let intersection = a.intersect(b);
+    if !intersection.is_positive() {
+        return 0.0;
+    }
+    let overlap = intersection.area();
+    overlap / (a.area() + b.area() - overlap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::tests::image;
+    use crate::project::tests::segment;
+
+    fn labels() -> HashMap<u32, Label> {
+        let label = |name: &str| Label {
+            name: name.to_string(),
+            color: egui::Color32::RED,
+            custom_color: false,
+            visible: true,
+        };
+        [(0, label("car")), (1, label("person"))]
+            .into_iter()
+            .collect()
+    }
+
+    fn problems_of(segments: Vec<crate::project::Segment>) -> Vec<String> {
+        problems(&image(std::path::Path::new("/"), segments), &labels())
+    }
+
+    #[test]
+    fn clean_boxes_have_no_problems() {
+        let segments = vec![
+            segment(0, egui::pos2(0.25, 0.25), egui::vec2(0.2, 0.2)),
+            segment(1, egui::pos2(0.75, 0.75), egui::vec2(0.2, 0.2)),
+            // Overlapping boxes of different classes are fine.
+            segment(1, egui::pos2(0.25, 0.25), egui::vec2(0.2, 0.2)),
+            // Touching the border within rounding.
+            segment(0, egui::pos2(0.1, 0.5), egui::vec2(0.2005, 0.2)),
+        ];
+        assert!(problems_of(segments).is_empty());
+    }
+
+    #[test]
+    fn finds_each_kind_of_problem() {
+        let segments = vec![
+            segment(7, egui::pos2(0.5, 0.5), egui::vec2(0.1, 0.1)),
+            segment(0, egui::pos2(0.5, 0.5), egui::vec2(0.0, 0.1)),
+            segment(0, egui::pos2(0.95, 0.5), egui::vec2(0.2, 0.1)),
+            segment(1, egui::pos2(0.3, 0.3), egui::vec2(0.2, 0.2)),
+            segment(1, egui::pos2(0.3, 0.3), egui::vec2(0.2, 0.201)),
+        ];
+        assert_eq!(
+            problems_of(segments),
+            [
+                "Box 0: unknown class 7",
+                "Box 1: empty",
+                "Box 2: outside the image",
+                "Boxes 3 and 4: duplicates",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_boxes_are_judged_by_pixel_size() {
+        let tiny = || vec![segment(0, egui::pos2(0.5, 0.5), egui::vec2(0.0005, 0.0005))];
+        let mut small = image(std::path::Path::new("/"), tiny());
+        small.pixel_size = Some(egui::vec2(640.0, 480.0));
+        assert_eq!(problems(&small, &labels()), ["Box 0: empty"]);
+
+        let mut large = image(std::path::Path::new("/"), tiny());
+        large.pixel_size = Some(egui::vec2(4000.0, 4000.0));
+        assert!(problems(&large, &labels()).is_empty());
+    }
+}
@@ -0,0 +1,87 @@
+use crate::attributes;
+use crate::attributes::AttributeValue;
+use crate::project::Project;
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Attributes Pascal VOC has elements of its own for.
+const VOC_FLAGS: [&str; 3] = ["truncated", "difficult", "occluded"];
+
+/// Writes a Pascal VOC annotation file for every image into `dir`. Images
+/// whose labels or size couldn't be read are left out. Boxes are in the same
+/// frame as the label files. Returns the number of files.
+pub fn export(project: &Project, dir: &Path) -> anyhow::Result<usize> {
+    let mut count = 0;
+    for image in &project.images {
+        let (true, Some(size)) = (image.labels_loaded, image.pixel_size) else {
+            continue;
+        };
+        let policy = project.orientation_policy;
+        let size = policy.stored_size(image.orientation, size);
+        let mut xml = String::new();
+        writeln!(xml, "<annotation>")?;
+        writeln!(xml, "  <filename>{}</filename>", escape(&image.file_name))?;
+        writeln!(xml, "  <size>")?;
+        writeln!(xml, "    <width>{}</width>", size.x as u32)?;
+        writeln!(xml, "    <height>{}</height>", size.y as u32)?;
+        writeln!(xml, "    <depth>3</depth>")?;
+        writeln!(xml, "  </size>")?;
+        for segment in &image.segments {
+            let name = project
+                .labels
+                .get(&segment.label_id)
+                .map_or(segment.label_id.to_string(), |l| l.name.clone());
+            let (center, box_size) =
+                policy.stored_box(image.orientation, segment.center, segment.size);
+            let min = ((center - box_size / 2.0).to_vec2() * size).max(egui::Vec2::ZERO);
+            let max = ((center + box_size / 2.0).to_vec2() * size).min(size);
+            let attributes = attributes::with_defaults(
+                &segment.attributes,
+                project.attribute_schema.get(&segment.label_id),
+            );
+            writeln!(xml, "  <object>")?;
+            writeln!(xml, "    <name>{}</name>", escape(&name))?;
+            writeln!(xml, "    <pose>Unspecified</pose>")?;
+            for flag in VOC_FLAGS {
+                let set = attributes.get(flag) == Some(&AttributeValue::Bool(true));
+                writeln!(xml, "    <{flag}>{}</{flag}>", set as u8)?;
+            }
+            writeln!(xml, "    <bndbox>")?;
+            writeln!(xml, "      <xmin>{}</xmin>", min.x.round())?;
+            writeln!(xml, "      <ymin>{}</ymin>", min.y.round())?;
+            writeln!(xml, "      <xmax>{}</xmax>", max.x.round())?;
+            writeln!(xml, "      <ymax>{}</ymax>", max.y.round())?;
+            writeln!(xml, "    </bndbox>")?;
+            let others: Vec<_> = attributes
+                .iter()
+                .filter(|(name, _)| !VOC_FLAGS.contains(&name.as_str()))
+                .collect();
+            if !others.is_empty() {
+                writeln!(xml, "    <attributes>")?;
+                for (name, value) in others {
+                    writeln!(xml, "      <attribute>")?;
+                    writeln!(xml, "        <name>{}</name>", escape(name))?;
+                    writeln!(xml, "        <value>{}</value>", escape(&value.to_string()))?;
+                    writeln!(xml, "      </attribute>")?;
+                }
+                writeln!(xml, "    </attributes>")?;
+            }
+            writeln!(xml, "  </object>")?;
+        }
+        writeln!(xml, "</annotation>")?;
+
+        let file_name = Path::new(&image.file_name).with_extension("xml");
+        fs::write(dir.join(file_name), xml)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}